@@ -0,0 +1,569 @@
+//! Off-chain quotes for the Couple prediction pools.
+//!
+//! A pool is a fixed product market maker over the yes and no tokens of a
+//! proposal. Buying adds the paid number, minus fees, to both reserves as a
+//! complete set and takes tokens of the bought side out until the product
+//! is restored. Selling does the reverse, so the returned number solves
+//! `(x + n - r) * (y - r) = x * y` for `r`. Removing liquidity returns a
+//! share of both reserves, less the withdrawal fee, and of the accrued fees.
+//! Fee rates are parts of `FEE_RATE_BASE`.
+
+use sp_core::U256;
+use subxt::Client;
+
+use crate::{
+	error::{Result, XPredictError},
+	pallets::{couple::*, Balance, CurrencyId, ProposalId},
+	runtime::XPredictRuntime,
+	XPredictLogic,
+};
+
+pub const FEE_RATE_BASE: u32 = 100_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolState {
+	pub proposal_id: ProposalId,
+	pub yes: CurrencyId,
+	pub no: CurrencyId,
+	pub yes_reserve: Balance,
+	pub no_reserve: Balance,
+	/// Liquidity shares issued by the pool
+	pub total_shares: Balance,
+	/// Fees accrued to the liquidity providers so far
+	pub total_fee: Balance,
+	/// Trading fee rate paid to the proposal owner
+	pub earn_fee: u32,
+	/// Trading fee rate paid to the liquidity providers
+	pub liquidity_fee: u32,
+	/// Fee rate kept from the outcome tokens of a liquidity removal
+	pub withdrawal_fee: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TradeQuote {
+	pub currency_id: CurrencyId,
+	/// Number paid in, proposal currency for buys, outcome tokens for sells
+	pub input: Balance,
+	/// Number received, outcome tokens for buys, proposal currency for sells
+	pub output: Balance,
+	pub owner_fee: Balance,
+	pub liquidity_fee: Balance,
+	/// Implied probability of `currency_id` before and after the trade
+	pub price_before: f64,
+	pub price_after: f64,
+	/// Proposal currency per outcome token, fees included
+	pub average_price: f64,
+	/// Relative distance between `average_price` and `price_before`
+	pub price_impact: f64,
+}
+
+impl TradeQuote {
+	pub fn fee(&self) -> Balance {
+		self.owner_fee.saturating_add(self.liquidity_fee)
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LiquidityQuote {
+	/// Shares minted by an add or burned by a remove
+	pub shares: Balance,
+	/// Outcome tokens handed to the provider, less the withdrawal fee
+	pub yes: Balance,
+	pub no: Balance,
+	/// Share of the accrued fees paid out on removal
+	pub fee_reward: Balance,
+}
+
+/// Client side slippage protection, the Couple calls take no minimum.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TradeLimit {
+	/// Least number the trade may return
+	pub min_output: Balance,
+	/// Highest implied probability of the bought side after a buy, lowest
+	/// of the sold side after a sell
+	pub price_bound: Option<f64>,
+}
+
+impl TradeLimit {
+	/// Fails if `quote` returns less than the minimum or moves the price
+	/// past the bound.
+	pub fn check(&self, proposal_id: ProposalId, quote: &TradeQuote, buy: bool) -> Result<()> {
+		self.check_output(proposal_id, quote.output)?;
+		match self.price_bound {
+			Some(bound)
+				if (buy && quote.price_after > bound) || (!buy && quote.price_after < bound) =>
+			{
+				Err(XPredictError::PriceBound {
+					proposal_id,
+					price: quote.price_after,
+					bound,
+				})
+			}
+			_ => Ok(()),
+		}
+	}
+
+	pub fn check_output(&self, proposal_id: ProposalId, output: Balance) -> Result<()> {
+		if output < self.min_output {
+			return Err(XPredictError::BelowMinimum {
+				proposal_id,
+				output,
+				minimum: self.min_output,
+			});
+		}
+		Ok(())
+	}
+}
+
+impl PoolState {
+	pub async fn fetch(client: &Client<XPredictRuntime>, proposal_id: ProposalId) -> Result<Self> {
+		let (yes, no) = XPredictLogic::proposal_pairs(client, proposal_id).await?;
+		let (yes_reserve, no_reserve) =
+			XPredictLogic::proposal_optional(client, proposal_id).await?;
+		let total_shares = client
+			.proposal_total_market(proposal_id, None)
+			.await?
+			.unwrap_or(0);
+		let total_fee = client
+			.proposal_total_market_fee(proposal_id, None)
+			.await?
+			.unwrap_or(0);
+		let earn_fee = client
+			.proposal_total_earn_trading_fee(proposal_id, None)
+			.await?
+			.unwrap_or(0);
+		let liquidity_fee = client
+			.proposal_liquidity_provider_fee_rate(None)
+			.await?
+			.unwrap_or(0);
+		let withdrawal_fee = client
+			.proposal_withdrawal_fee_rate(None)
+			.await?
+			.unwrap_or(0);
+		Ok(PoolState {
+			proposal_id,
+			yes,
+			no,
+			yes_reserve,
+			no_reserve,
+			total_shares,
+			total_fee,
+			earn_fee,
+			liquidity_fee,
+			withdrawal_fee,
+		})
+	}
+
+	/// Implied probabilities of yes and no, the opposite reserve over both.
+	pub fn probabilities(&self) -> (f64, f64) {
+		probabilities(self.yes_reserve, self.no_reserve)
+	}
+
+	/// Outcome tokens of `currency_id` received for paying `number`.
+	pub fn quote_buy(&self, currency_id: CurrencyId, number: Balance) -> Result<TradeQuote> {
+		let (reserve, other) = self.reserves(currency_id)?;
+		let (owner_fee, liquidity_fee) = self.fees(number);
+		let paid = number.saturating_sub(owner_fee.saturating_add(liquidity_fee));
+		if paid == 0 {
+			return Err(XPredictError::Quote(
+				"number does not cover the fees".into(),
+			));
+		}
+		// reserve * other = (reserve + paid - output) * (other + paid)
+		let product = U256::from(reserve) * U256::from(other);
+		let new_reserve = ceil_div(product, U256::from(other) + U256::from(paid));
+		let output = to_balance(U256::from(reserve) + U256::from(paid) - new_reserve)?;
+		if output == 0 {
+			return Err(XPredictError::Quote("number buys no tokens".into()));
+		}
+		let (new_reserve, new_other) = (
+			reserve.saturating_add(paid).saturating_sub(output),
+			other.saturating_add(paid),
+		);
+		Ok(self.trade_quote(
+			currency_id,
+			number,
+			output,
+			owner_fee,
+			liquidity_fee,
+			(reserve, other),
+			(new_reserve, new_other),
+			number as f64 / output as f64,
+		))
+	}
+
+	/// Proposal currency received for selling `number` outcome tokens of
+	/// `currency_id`.
+	pub fn quote_sell(&self, currency_id: CurrencyId, number: Balance) -> Result<TradeQuote> {
+		let (reserve, other) = self.reserves(currency_id)?;
+		if number == 0 {
+			return Err(XPredictError::Quote("nothing to sell".into()));
+		}
+		// (reserve + number - r) * (other - r) = reserve * other, smaller root
+		let sum = U256::from(reserve) + U256::from(number) + U256::from(other);
+		let discriminant = sum * sum - U256::from(4) * U256::from(number) * U256::from(other);
+		let returned = to_balance((sum - discriminant.integer_sqrt()) / 2)?;
+		let (owner_fee, liquidity_fee) = self.fees(returned);
+		let output = returned.saturating_sub(owner_fee.saturating_add(liquidity_fee));
+		if output == 0 {
+			return Err(XPredictError::Quote(
+				"number returns nothing after the fees".into(),
+			));
+		}
+		let (new_reserve, new_other) = (
+			reserve.saturating_add(number).saturating_sub(returned),
+			other.saturating_sub(returned),
+		);
+		Ok(self.trade_quote(
+			currency_id,
+			number,
+			output,
+			owner_fee,
+			liquidity_fee,
+			(reserve, other),
+			(new_reserve, new_other),
+			output as f64 / number as f64,
+		))
+	}
+
+	/// Shares minted for adding `number` of the proposal currency. The side
+	/// the pool holds less of is topped up in proportion and the rest of the
+	/// complete set is returned to the provider.
+	pub fn quote_add_liquidity(&self, number: Balance) -> Result<LiquidityQuote> {
+		let largest = self.yes_reserve.max(self.no_reserve);
+		if largest == 0 || self.total_shares == 0 {
+			return Ok(LiquidityQuote {
+				shares: number,
+				yes: 0,
+				no: 0,
+				fee_reward: 0,
+			});
+		}
+		let scale = |value: Balance| mul_div(number, value, largest);
+		Ok(LiquidityQuote {
+			shares: scale(self.total_shares)?,
+			yes: number - scale(self.yes_reserve)?,
+			no: number - scale(self.no_reserve)?,
+			fee_reward: 0,
+		})
+	}
+
+	/// Outcome tokens and fee reward returned for burning `shares`.
+	pub fn quote_remove_liquidity(&self, shares: Balance) -> Result<LiquidityQuote> {
+		if shares == 0 || self.total_shares == 0 {
+			return Ok(LiquidityQuote {
+				shares: 0,
+				yes: 0,
+				no: 0,
+				fee_reward: 0,
+			});
+		}
+		if shares > self.total_shares {
+			return Err(XPredictError::Quote(format!(
+				"{} shares requested, the pool has {}",
+				shares, self.total_shares
+			)));
+		}
+		let scale = |value: Balance| mul_div(shares, value, self.total_shares);
+		let withdrawn = |reserve: Balance| -> Result<Balance> {
+			let number = scale(reserve)?;
+			Ok(number - fee(number, self.withdrawal_fee))
+		};
+		Ok(LiquidityQuote {
+			shares,
+			yes: withdrawn(self.yes_reserve)?,
+			no: withdrawn(self.no_reserve)?,
+			fee_reward: scale(self.total_fee)?,
+		})
+	}
+
+	/// Reserves of `currency_id` and of the opposite side.
+	fn reserves(&self, currency_id: CurrencyId) -> Result<(Balance, Balance)> {
+		let reserves = if currency_id == self.yes {
+			(self.yes_reserve, self.no_reserve)
+		} else if currency_id == self.no {
+			(self.no_reserve, self.yes_reserve)
+		} else {
+			return Err(XPredictError::Quote(format!(
+				"currency {} is not traded by proposal {}",
+				currency_id, self.proposal_id
+			)));
+		};
+		if reserves.0 == 0 || reserves.1 == 0 {
+			return Err(XPredictError::Quote(format!(
+				"proposal {} has no liquidity",
+				self.proposal_id
+			)));
+		}
+		Ok(reserves)
+	}
+
+	fn fees(&self, number: Balance) -> (Balance, Balance) {
+		(fee(number, self.earn_fee), fee(number, self.liquidity_fee))
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	fn trade_quote(
+		&self,
+		currency_id: CurrencyId,
+		input: Balance,
+		output: Balance,
+		owner_fee: Balance,
+		liquidity_fee: Balance,
+		before: (Balance, Balance),
+		after: (Balance, Balance),
+		average_price: f64,
+	) -> TradeQuote {
+		let price_before = probabilities(before.0, before.1).0;
+		let price_after = probabilities(after.0, after.1).0;
+		TradeQuote {
+			currency_id,
+			input,
+			output,
+			owner_fee,
+			liquidity_fee,
+			price_before,
+			price_after,
+			average_price,
+			price_impact: (average_price - price_before).abs() / price_before,
+		}
+	}
+}
+
+/// Probability of the side holding `reserve` against `other`, and of the
+/// opposite side.
+fn probabilities(reserve: Balance, other: Balance) -> (f64, f64) {
+	let total = reserve as f64 + other as f64;
+	if total == 0.0 {
+		return (0.5, 0.5);
+	}
+	(other as f64 / total, reserve as f64 / total)
+}
+
+/// The part `rate` of `number`, all of it for rates past `FEE_RATE_BASE`.
+fn fee(number: Balance, rate: u32) -> Balance {
+	mul_div(number, rate.into(), FEE_RATE_BASE.into())
+		.unwrap_or(number)
+		.min(number)
+}
+
+fn mul_div(a: Balance, b: Balance, c: Balance) -> Result<Balance> {
+	to_balance(U256::from(a) * U256::from(b) / U256::from(c))
+}
+
+fn ceil_div(a: U256, b: U256) -> U256 {
+	(a + b - 1) / b
+}
+
+fn to_balance(value: U256) -> Result<Balance> {
+	if value > U256::from(Balance::MAX) {
+		return Err(XPredictError::Quote(
+			"amount overflows the balance type".into(),
+		));
+	}
+	Ok(value.low_u128())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pool(yes_reserve: Balance, no_reserve: Balance) -> PoolState {
+		PoolState {
+			proposal_id: 0,
+			yes: 1,
+			no: 2,
+			yes_reserve,
+			no_reserve,
+			total_shares: yes_reserve.max(no_reserve),
+			total_fee: 0,
+			earn_fee: 0,
+			liquidity_fee: 0,
+			withdrawal_fee: 0,
+		}
+	}
+
+	#[test]
+	fn ceil_div_rounds_up() {
+		assert_eq!(ceil_div(U256::from(6), U256::from(2)), U256::from(3));
+		assert_eq!(ceil_div(U256::from(7), U256::from(2)), U256::from(4));
+		assert_eq!(ceil_div(U256::from(1), U256::from(3)), U256::from(1));
+		assert_eq!(ceil_div(U256::from(0), U256::from(3)), U256::from(0));
+	}
+
+	#[test]
+	fn buy_keeps_the_product() {
+		let pool = pool(1000, 1000);
+		let quote = pool.quote_buy(pool.yes, 100).unwrap();
+		assert_eq!(quote.output, 190);
+		let (yes, no) = (1000 + 100 - quote.output, 1000 + 100);
+		assert!(yes * no >= 1000 * 1000);
+		assert!(quote.price_after > quote.price_before);
+		assert_eq!(quote.price_before, 0.5);
+	}
+
+	#[test]
+	fn sell_after_buy_returns_at_most_the_paid_number() {
+		let before = pool(1000, 1000);
+		let buy = before.quote_buy(before.yes, 100).unwrap();
+		let after = pool(1000 + 100 - buy.output, 1000 + 100);
+		let sell = after.quote_sell(after.yes, buy.output).unwrap();
+		assert!(sell.output <= 100);
+		assert!(sell.output >= 99);
+		assert!(sell.price_after < sell.price_before);
+	}
+
+	#[test]
+	fn fees_split_between_owner_and_providers() {
+		let mut pool = pool(1_000_000, 1_000_000);
+		pool.earn_fee = 2_000;
+		pool.liquidity_fee = 1_000;
+		let buy = pool.quote_buy(pool.no, 10_000).unwrap();
+		assert_eq!((buy.owner_fee, buy.liquidity_fee), (200, 100));
+		assert_eq!(buy.fee(), 300);
+		let without_fees = {
+			let mut free = pool;
+			free.earn_fee = 0;
+			free.liquidity_fee = 0;
+			free.quote_buy(free.no, 10_000 - 300).unwrap()
+		};
+		assert_eq!(buy.output, without_fees.output);
+
+		let sell = pool.quote_sell(pool.no, 10_000).unwrap();
+		let returned = sell.output + sell.fee();
+		assert_eq!(sell.owner_fee, returned * 2 / 100);
+		assert_eq!(sell.liquidity_fee, returned / 100);
+	}
+
+	#[test]
+	fn buy_below_the_fees_is_rejected() {
+		let mut pool = pool(1000, 1000);
+		pool.earn_fee = FEE_RATE_BASE;
+		assert!(matches!(
+			pool.quote_buy(pool.yes, 10),
+			Err(XPredictError::Quote(_))
+		));
+	}
+
+	#[test]
+	fn empty_pool_cannot_be_traded() {
+		let pool = pool(0, 0);
+		assert!(pool.quote_buy(pool.yes, 100).is_err());
+		assert!(pool.quote_sell(pool.no, 100).is_err());
+		assert!(pool.quote_buy(3, 100).is_err());
+		assert_eq!(pool.probabilities(), (0.5, 0.5));
+	}
+
+	#[test]
+	fn first_liquidity_mints_the_number_added() {
+		let quote = pool(0, 0).quote_add_liquidity(500).unwrap();
+		assert_eq!(
+			quote,
+			LiquidityQuote {
+				shares: 500,
+				yes: 0,
+				no: 0,
+				fee_reward: 0,
+			}
+		);
+	}
+
+	#[test]
+	fn added_liquidity_returns_the_surplus_side() {
+		let mut pool = pool(500, 2000);
+		pool.total_shares = 1000;
+		let quote = pool.quote_add_liquidity(200).unwrap();
+		assert_eq!(quote.shares, 100);
+		assert_eq!((quote.yes, quote.no), (150, 0));
+	}
+
+	#[test]
+	fn removed_liquidity_is_proportional() {
+		let mut pool = pool(1000, 3000);
+		pool.total_shares = 2000;
+		pool.total_fee = 40;
+		let quote = pool.quote_remove_liquidity(500).unwrap();
+		assert_eq!((quote.yes, quote.no, quote.fee_reward), (250, 750, 10));
+		assert!(pool.quote_remove_liquidity(2001).is_err());
+	}
+
+	#[test]
+	fn removed_liquidity_pays_the_withdrawal_fee() {
+		let mut pool = pool(1000, 3000);
+		pool.total_shares = 2000;
+		pool.total_fee = 40;
+		pool.withdrawal_fee = 2_000;
+		let quote = pool.quote_remove_liquidity(500).unwrap();
+		assert_eq!((quote.yes, quote.no), (245, 735));
+		assert_eq!(quote.fee_reward, 10);
+
+		pool.withdrawal_fee = FEE_RATE_BASE * 2;
+		let quote = pool.quote_remove_liquidity(500).unwrap();
+		assert_eq!((quote.yes, quote.no), (0, 0));
+	}
+
+	#[test]
+	fn sells_returning_nothing_are_rejected() {
+		let mut pool = pool(1000, 1000);
+		for number in [0, 1] {
+			assert!(matches!(
+				pool.quote_sell(pool.yes, number),
+				Err(XPredictError::Quote(_))
+			));
+		}
+		let sell = pool.quote_sell(pool.yes, 2).unwrap();
+		assert!(sell.average_price.is_finite() && sell.price_impact.is_finite());
+
+		pool.earn_fee = FEE_RATE_BASE;
+		assert!(matches!(
+			pool.quote_sell(pool.yes, 100),
+			Err(XPredictError::Quote(_))
+		));
+	}
+
+	#[test]
+	fn zero_shares_remove_nothing() {
+		let empty = LiquidityQuote {
+			shares: 0,
+			yes: 0,
+			no: 0,
+			fee_reward: 0,
+		};
+		let mut drained = pool(0, 0);
+		drained.total_shares = 0;
+		assert_eq!(drained.quote_remove_liquidity(0).unwrap(), empty);
+		assert_eq!(drained.quote_remove_liquidity(10).unwrap(), empty);
+		assert_eq!(pool(1000, 1000).quote_remove_liquidity(0).unwrap(), empty);
+	}
+
+	#[test]
+	fn trade_limit_bounds() {
+		let pool = pool(1000, 1000);
+		let buy = pool.quote_buy(pool.yes, 100).unwrap();
+		let limit = |min_output, price_bound| TradeLimit {
+			min_output,
+			price_bound,
+		};
+		assert!(limit(190, None).check(0, &buy, true).is_ok());
+		assert!(matches!(
+			limit(191, None).check(0, &buy, true),
+			Err(XPredictError::BelowMinimum {
+				output: 190,
+				minimum: 191,
+				..
+			})
+		));
+		assert!(limit(0, Some(buy.price_after)).check(0, &buy, true).is_ok());
+		assert!(matches!(
+			limit(0, Some(0.54)).check(0, &buy, true),
+			Err(XPredictError::PriceBound { .. })
+		));
+
+		let sell = pool.quote_sell(pool.yes, 100).unwrap();
+		assert!(limit(0, Some(0.4)).check(0, &sell, false).is_ok());
+		assert!(matches!(
+			limit(0, Some(0.49)).check(0, &sell, false),
+			Err(XPredictError::PriceBound { .. })
+		));
+	}
+}
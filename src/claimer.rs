@@ -0,0 +1,268 @@
+//! Finds the rewards, stakes and winnings an account can claim across every
+//! proposal and claims them in `Utility::batch` extrinsics.
+
+use std::marker::PhantomData;
+
+use serde_json::{json, Value};
+use subxt::{system::System, Client, Encoded};
+
+use crate::{
+	error::{Result, XPredictError},
+	logic::{batch, XPredictLogic},
+	pairs::XPredictSigner,
+	pallets::{
+		autonomy::TakeOutCall,
+		couple::{self, RetrievalCall},
+		proposals, Balance, CurrencyId, ProposalId, ProposalStatus,
+	},
+	runtime::XPredictRuntime,
+};
+
+type AccountId = <XPredictRuntime as System>::AccountId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Claim {
+	/// `couple::WithdrawalRewardCall` for liquidity, owner fee and autonomy
+	/// rewards of an ended proposal
+	PoolReward { proposal_id: ProposalId },
+	/// `proposals::WithdrawalRewardCall` for a vote stake and its reward
+	VoteReward {
+		proposal_id: ProposalId,
+		stake: Balance,
+	},
+	/// `RetrievalCall` of the winning tokens held
+	Retrieval {
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+		number: Balance,
+	},
+	/// `TakeOutCall` of the stake on a report against `target`
+	TakeOut {
+		proposal_id: ProposalId,
+		target: AccountId,
+		stake: Balance,
+	},
+}
+
+impl Claim {
+	pub fn proposal_id(&self) -> ProposalId {
+		match self {
+			Claim::PoolReward { proposal_id }
+			| Claim::VoteReward { proposal_id, .. }
+			| Claim::Retrieval { proposal_id, .. }
+			| Claim::TakeOut { proposal_id, .. } => *proposal_id,
+		}
+	}
+
+	fn encode(&self, client: &Client<XPredictRuntime>) -> Result<Encoded> {
+		let encoded = match self {
+			Claim::PoolReward { proposal_id } => client.encode(couple::WithdrawalRewardCall {
+				proposal_id: *proposal_id,
+				_runtime: PhantomData,
+			})?,
+			Claim::VoteReward { proposal_id, .. } => {
+				client.encode(proposals::WithdrawalRewardCall {
+					proposal_id: *proposal_id,
+					_runtime: PhantomData,
+				})?
+			}
+			Claim::Retrieval {
+				proposal_id,
+				currency_id,
+				number,
+			} => client.encode(RetrievalCall {
+				proposal_id: *proposal_id,
+				currency_id: *currency_id,
+				number: *number,
+				_runtime: PhantomData,
+			})?,
+			Claim::TakeOut {
+				proposal_id,
+				target,
+				..
+			} => client.encode(TakeOutCall::<XPredictRuntime> {
+				proposal_id: *proposal_id,
+				target,
+			})?,
+		};
+		Ok(encoded)
+	}
+
+	pub fn to_json(&self) -> Value {
+		match self {
+			Claim::PoolReward { proposal_id } => json!({
+				"proposal_id": proposal_id,
+				"claim": "pool_reward",
+			}),
+			Claim::VoteReward { proposal_id, stake } => json!({
+				"proposal_id": proposal_id,
+				"claim": "vote_reward",
+				"stake": stake.to_string(),
+			}),
+			Claim::Retrieval {
+				proposal_id,
+				currency_id,
+				number,
+			} => json!({
+				"proposal_id": proposal_id,
+				"claim": "retrieval",
+				"currency_id": currency_id,
+				"number": number.to_string(),
+			}),
+			Claim::TakeOut {
+				proposal_id,
+				target,
+				stake,
+			} => json!({
+				"proposal_id": proposal_id,
+				"claim": "take_out",
+				"target": target.to_string(),
+				"stake": stake.to_string(),
+			}),
+		}
+	}
+}
+
+/// Everything `account` can claim. Report stakes are only looked up against
+/// `targets`, the uploaders the account may have reported. The chain keeps no
+/// record of the owner fee accrued, so owned proposals are only claimed for it
+/// with `owner_fees`.
+pub async fn claims(
+	client: &Client<XPredictRuntime>,
+	account: &AccountId,
+	targets: &[AccountId],
+	owner_fees: bool,
+) -> Result<Vec<Claim>> {
+	let mut claims = Vec::new();
+	for proposal_id in 0..XPredictLogic::proposal_count(client).await? {
+		if XPredictLogic::proposal_status(client, proposal_id).await? != ProposalStatus::End {
+			continue;
+		}
+		if let Some((stake, _)) =
+			XPredictLogic::proposal_vote_stake(client, proposal_id, account).await?
+		{
+			claims.push(Claim::VoteReward { proposal_id, stake });
+		}
+		if pool_reward(client, proposal_id, account, owner_fees).await? {
+			claims.push(Claim::PoolReward { proposal_id });
+		}
+		if let Some(currency_id) = XPredictLogic::proposal_result(client, proposal_id).await? {
+			let number = XPredictLogic::balance_of(client, currency_id, account).await?;
+			if number > 0 {
+				claims.push(Claim::Retrieval {
+					proposal_id,
+					currency_id,
+					number,
+				});
+			}
+		}
+		if XPredictLogic::autonomy_report_staked(client, proposal_id, account).await? == 0 {
+			continue;
+		}
+		for target in targets {
+			let reports =
+				XPredictLogic::autonomy_report_account(client, proposal_id, target).await?;
+			if let Some((_, stake)) = reports.get(account) {
+				claims.push(Claim::TakeOut {
+					proposal_id,
+					target: target.clone(),
+					stake: *stake,
+				});
+			}
+		}
+	}
+	Ok(claims)
+}
+
+/// Whether `account` has liquidity or a pending autonomy reward in an ended
+/// proposal or, with `owner_fees`, owns it and never withdrew its fee.
+async fn pool_reward(
+	client: &Client<XPredictRuntime>,
+	proposal_id: ProposalId,
+	account: &AccountId,
+	owner_fees: bool,
+) -> Result<bool> {
+	if XPredictLogic::proposal_liquidity_of(client, proposal_id, account).await? > 0 {
+		return Ok(true);
+	}
+	if XPredictLogic::proposal_pending_autonomy_reward(client, proposal_id, account).await? > 0 {
+		return Ok(true);
+	}
+	if !owner_fees
+		|| XPredictLogic::proposal_owner(client, proposal_id)
+			.await?
+			.as_ref() != Some(account)
+	{
+		return Ok(false);
+	}
+	let withdrawn =
+		XPredictLogic::proposal_owner_withdrawn_fee(client, proposal_id, account).await?;
+	Ok(withdrawn == 0)
+}
+
+/// Outcome of one batch of claims.
+#[derive(Debug)]
+pub struct BatchReport<'a> {
+	/// Claims dispatched successfully
+	pub claimed: &'a [Claim],
+	/// Claim the batch was interrupted at and its dispatch error
+	pub failed: Option<(&'a Claim, String)>,
+	/// Claims that did not run. They are resubmitted in the next batch after
+	/// a failed claim, and dropped when the extrinsic itself failed.
+	pub not_run: &'a [Claim],
+	/// Why the extrinsic failed as a whole
+	pub error: Option<String>,
+}
+
+/// Submits `claims` in batches of `batch_size`, reporting each batch to
+/// `on_batch`. `Utility::batch` stops at the first failing call, the claims
+/// after it are submitted again without it. A failed batch does not stop
+/// the following ones.
+pub async fn claim(
+	client: &Client<XPredictRuntime>,
+	signer: &XPredictSigner,
+	claims: &[Claim],
+	batch_size: usize,
+	mut on_batch: impl FnMut(&BatchReport),
+) -> Result<()> {
+	for chunk in claims.chunks(batch_size.max(1)) {
+		let mut pending = chunk;
+		while !pending.is_empty() {
+			let calls = pending
+				.iter()
+				.map(|claim| claim.encode(client))
+				.collect::<Result<Vec<_>>>()?;
+			match batch(client, signer, calls).await {
+				Ok(_) => {
+					on_batch(&BatchReport {
+						claimed: pending,
+						failed: None,
+						not_run: &[],
+						error: None,
+					});
+					pending = &[];
+				}
+				Err(XPredictError::BatchInterrupted { index, error }) => {
+					let index = (index as usize).min(pending.len() - 1);
+					on_batch(&BatchReport {
+						claimed: &pending[..index],
+						failed: Some((&pending[index], error.to_string())),
+						not_run: &pending[index + 1..],
+						error: None,
+					});
+					pending = &pending[index + 1..];
+				}
+				Err(e) => {
+					on_batch(&BatchReport {
+						claimed: &[],
+						failed: None,
+						not_run: pending,
+						error: Some(e.to_string()),
+					});
+					pending = &[];
+				}
+			}
+		}
+	}
+	Ok(())
+}
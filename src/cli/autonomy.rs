@@ -0,0 +1,215 @@
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+use super::{output::submitted, proposals::Opinion, Context};
+use ws_client::{
+	error::{Result, XPredictError},
+	keystore::KeyScheme,
+	logic::{expect_event, sudo, XPredictLogic},
+	pallets::{autonomy::*, Balance, CurrencyId, ProposalId},
+	runtime::AccountId,
+};
+
+#[derive(Debug, StructOpt)]
+pub enum AutonomyCommand {
+	/// Stake the minimal number to become a result uploader
+	Stake,
+	/// Take back the uploader stake
+	Unstake,
+	/// Report a dishonest result uploader
+	Report {
+		proposal_id: ProposalId,
+		target: AccountId,
+		number: Balance,
+	},
+	/// Second (`yes`) or oppose (`no`) an existing report
+	SecondedReport {
+		proposal_id: ProposalId,
+		target: AccountId,
+		number: Balance,
+		support: Opinion,
+	},
+	/// Take out the report stakes once the report is settled
+	TakeOut {
+		proposal_id: ProposalId,
+		target: AccountId,
+	},
+	/// Tag an account as result uploader (sudo)
+	Tag { target: AccountId },
+	/// Remove the uploader tag of an account (sudo)
+	Untag { account: AccountId },
+	/// Upload the result of a proposal as an unsigned extrinsic
+	UploadResult {
+		proposal_id: ProposalId,
+		result: CurrencyId,
+	},
+	/// Show the reports on an uploader, their tallies and the report window
+	Reports {
+		proposal_id: ProposalId,
+		target: AccountId,
+	},
+	/// Show the uploader stake and tag of an account
+	Account { account: AccountId },
+}
+
+impl AutonomyCommand {
+	pub async fn run(self, ctx: &Context) -> Result<Value> {
+		let client = &ctx.client;
+		match self {
+			AutonomyCommand::Stake => {
+				let signer = ctx.signer()?;
+				XPredictLogic::autonomy_stake(client, &signer).await?;
+				Ok(json!({ "staked": true }))
+			}
+			AutonomyCommand::Unstake => {
+				let result = client.un_stake_and_watch(&ctx.signer()?).await?;
+				let event = expect_event::<UnStakeEvent<_>>(&result)?;
+				Ok(submitted(
+					&result,
+					json!({
+						"from": event.from.to_string(),
+						"number": event.number.to_string(),
+					}),
+				))
+			}
+			AutonomyCommand::Report {
+				proposal_id,
+				target,
+				number,
+			} => {
+				let number = XPredictLogic::autonomy_report(
+					client,
+					&ctx.signer()?,
+					proposal_id,
+					&target,
+					number,
+				)
+				.await?;
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"target": target.to_string(),
+					"number": number.to_string(),
+				}))
+			}
+			AutonomyCommand::SecondedReport {
+				proposal_id,
+				target,
+				number,
+				support,
+			} => {
+				let approve = XPredictLogic::autonomy_seconded_report(
+					client,
+					&ctx.signer()?,
+					proposal_id,
+					&target,
+					number,
+					support.into(),
+				)
+				.await?;
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"target": target.to_string(),
+					"number": number.to_string(),
+					"approve": approve,
+				}))
+			}
+			AutonomyCommand::TakeOut {
+				proposal_id,
+				target,
+			} => {
+				let number =
+					XPredictLogic::autonomy_take_out(client, &ctx.signer()?, proposal_id, &target)
+						.await?;
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"target": target.to_string(),
+					"number": number.to_string(),
+				}))
+			}
+			AutonomyCommand::Tag { target } => {
+				XPredictLogic::autonomy_tag(client, &ctx.signer()?, &target).await?;
+				Ok(json!({ "tagged": target.to_string() }))
+			}
+			AutonomyCommand::Untag { account } => {
+				let call = client.encode(UntaggingCall { account: &account })?;
+				let result = sudo(client, &ctx.signer()?, &call).await?;
+				let event = expect_event::<UnTaggingEvent<_>>(&result)?;
+				Ok(submitted(
+					&result,
+					json!({ "untagged": event.who.to_string() }),
+				))
+			}
+			AutonomyCommand::UploadResult {
+				proposal_id,
+				result,
+			} => {
+				match ctx.scheme {
+					KeyScheme::Sr25519 => {
+						let pair = ctx.pair(&ctx.keystore)?;
+						XPredictLogic::autonomy_upload(client, pair, proposal_id, result).await?
+					}
+					KeyScheme::Ed25519 => {
+						let pair = ctx.pair(&ctx.ed25519)?;
+						XPredictLogic::autonomy_upload(client, pair, proposal_id, result).await?
+					}
+					KeyScheme::Ecdsa => {
+						let pair = ctx.pair(&ctx.ecdsa)?;
+						XPredictLogic::autonomy_upload(client, pair, proposal_id, result).await?
+					}
+				}
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"result": result,
+				}))
+			}
+			AutonomyCommand::Reports {
+				proposal_id,
+				target,
+			} => {
+				let reporters =
+					XPredictLogic::autonomy_report_account(client, proposal_id, &target).await?;
+				let (approve, oppose) =
+					XPredictLogic::autonomy_statistical_report(client, proposal_id).await?;
+				let minimal = XPredictLogic::autonomy_minimal_report_number(client).await?;
+				let interval = XPredictLogic::autonomy_report_interval(client).await?;
+				let report_time =
+					match XPredictLogic::autonomy_report_time(client, proposal_id).await {
+						Ok(time) => Some(time),
+						Err(XPredictError::StorageNotFound { .. }) => None,
+						Err(e) => return Err(e),
+					};
+				let reporters = reporters
+					.into_iter()
+					.map(|(account, (approve, number))| {
+						json!({
+							"account": account.to_string(),
+							"approve": approve,
+							"number": number.to_string(),
+						})
+					})
+					.collect::<Vec<_>>();
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"target": target.to_string(),
+					"reporters": reporters,
+					"approve": approve.to_string(),
+					"oppose": oppose.to_string(),
+					"minimal_report_number": minimal.to_string(),
+					"report_time": report_time,
+					"report_interval": interval,
+				}))
+			}
+			AutonomyCommand::Account { account } => {
+				let staked = XPredictLogic::autonomy_stake_account(client, &account).await?;
+				let tagged = XPredictLogic::autonomy_account(client, &account)
+					.await
+					.is_ok();
+				Ok(json!({
+					"account": account.to_string(),
+					"staked": staked.to_string(),
+					"tagged": tagged,
+				}))
+			}
+		}
+	}
+}
@@ -0,0 +1,71 @@
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+use super::{Context, OutputFormat};
+use ws_client::{
+	claimer::{claim, claims, Claim},
+	error::{Result, XPredictError},
+	listing::result_uploaders,
+	runtime::AccountId,
+};
+
+#[derive(Debug, StructOpt)]
+pub struct ClaimCommand {
+	/// Account to look up, defaults to the `--signer` account
+	#[structopt(long)]
+	account: Option<AccountId>,
+	/// Uploaders the account may have reported, for report stakes; every
+	/// tagged account when none is given
+	#[structopt(long = "target")]
+	targets: Vec<AccountId>,
+	/// Also claim the trading fee of owned proposals whose fee was never
+	/// withdrawn; the chain does not tell whether any is left
+	#[structopt(long)]
+	owner_fees: bool,
+	/// Claims per `Utility::batch` extrinsic
+	#[structopt(long, default_value = "10")]
+	batch_size: usize,
+	/// List the claims without submitting them
+	#[structopt(long)]
+	dry_run: bool,
+}
+
+impl ClaimCommand {
+	/// Prints the claims found, then the outcome of every batch.
+	pub async fn run(self, ctx: &Context, output: OutputFormat) -> Result<()> {
+		let signer = ctx.signer()?;
+		let account = self.account.unwrap_or_else(|| signer.account_id().clone());
+		// the claim calls act on their origin, so only the signer's own claims
+		// can be submitted
+		if !self.dry_run && &account != signer.account_id() {
+			return Err(XPredictError::Config(format!(
+				"claims of {} can only be listed with --dry-run, --signer is {}",
+				account,
+				signer.account_id()
+			)));
+		}
+		let targets = if self.targets.is_empty() {
+			let uploaders = result_uploaders(&ctx.client, 100).await?;
+			uploaders.into_iter().map(|row| row.account).collect()
+		} else {
+			self.targets
+		};
+		let found = claims(&ctx.client, &account, &targets, self.owner_fees).await?;
+		output.print(&Value::Array(found.iter().map(Claim::to_json).collect()));
+		if self.dry_run || found.is_empty() {
+			return Ok(());
+		}
+		claim(&ctx.client, &*signer, &found, self.batch_size, |report| {
+			output.print(&json!({
+				"claimed": report.claimed.iter().map(Claim::to_json).collect::<Vec<_>>(),
+				"failed": report.failed.as_ref().map(|(claim, error)| json!({
+					"claim": claim.to_json(),
+					"error": error,
+				})),
+				"not_run": report.not_run.iter().map(Claim::to_json).collect::<Vec<_>>(),
+				"error": report.error,
+			}))
+		})
+		.await
+	}
+}
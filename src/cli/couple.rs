@@ -0,0 +1,403 @@
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+use super::{output::submitted, Context};
+use ws_client::{
+	amm::{LiquidityQuote, PoolState, TradeLimit, TradeQuote},
+	error::Result,
+	logic::{expect_event, XPredictLogic},
+	pallets::{couple::*, Balance, CategoryId, CurrencyId, ProposalId},
+};
+
+#[derive(Debug, StructOpt)]
+pub enum CoupleCommand {
+	/// Create a proposal and its yes/no pool
+	NewProposal {
+		title: String,
+		yes: String,
+		no: String,
+		category_id: CategoryId,
+		currency_id: CurrencyId,
+		number: Balance,
+		/// Seconds from now until the proposal closes, defaults to the config value
+		#[structopt(long)]
+		close_after_secs: Option<u64>,
+		/// Defaults to the config value
+		#[structopt(long)]
+		earn_fee: Option<u32>,
+		#[structopt(long, default_value = "")]
+		detail: String,
+	},
+	/// Buy yes or no tokens with the proposal currency
+	Buy {
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+		number: Balance,
+		/// Refuse the trade if fewer tokens would be bought
+		#[structopt(long)]
+		min_output: Option<Balance>,
+		/// Refuse the trade if the bought side's probability would exceed this
+		#[structopt(long)]
+		max_price: Option<f64>,
+	},
+	/// Sell yes or no tokens for the proposal currency
+	Sell {
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+		number: Balance,
+		/// Refuse the trade if less of the proposal currency would be returned
+		#[structopt(long)]
+		min_output: Option<Balance>,
+		/// Refuse the trade if the sold side's probability would fall below this
+		#[structopt(long)]
+		min_price: Option<f64>,
+	},
+	/// Add liquidity to the proposal pool
+	AddLiquidity {
+		proposal_id: ProposalId,
+		number: Balance,
+	},
+	/// Remove liquidity from the proposal pool
+	RemoveLiquidity {
+		proposal_id: ProposalId,
+		number: Balance,
+	},
+	/// Redeem winning tokens after the result is set
+	Retrieval {
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+		number: Balance,
+	},
+	/// Withdraw the liquidity provider reward
+	WithdrawalReward { proposal_id: ProposalId },
+	/// Set the result of a proposal (sudo)
+	SetResult {
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+	},
+	/// Show the pool pairs, status and market of a proposal
+	Info { proposal_id: ProposalId },
+	/// Quote a trade or liquidity change without submitting it
+	Quote(QuoteCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub enum QuoteCommand {
+	/// Tokens received for paying `number` of the proposal currency
+	Buy {
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+		number: Balance,
+	},
+	/// Proposal currency received for selling `number` tokens
+	Sell {
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+		number: Balance,
+	},
+	/// Shares minted and tokens returned for adding `number`
+	AddLiquidity {
+		proposal_id: ProposalId,
+		number: Balance,
+	},
+	/// Tokens and fee reward returned for burning `number` shares
+	RemoveLiquidity {
+		proposal_id: ProposalId,
+		number: Balance,
+	},
+}
+
+impl CoupleCommand {
+	pub async fn run(self, ctx: &Context) -> Result<Value> {
+		let client = &ctx.client;
+		match self {
+			CoupleCommand::NewProposal {
+				title,
+				yes,
+				no,
+				category_id,
+				currency_id,
+				number,
+				close_after_secs,
+				earn_fee,
+				detail,
+			} => {
+				let close_time = XPredictLogic::close_time_after(Duration::from_secs(
+					close_after_secs.unwrap_or(ctx.config.proposal.close_after_secs),
+				))?;
+				let result = client
+					.new_proposal_and_watch(
+						&ctx.signer()?,
+						title.into_bytes(),
+						[yes.into_bytes(), no.into_bytes()],
+						close_time,
+						category_id,
+						currency_id,
+						number,
+						earn_fee.unwrap_or(ctx.config.fees.earn_fee),
+						detail.into_bytes(),
+					)
+					.await?;
+				let event = expect_event::<NewProposalEvent<_>>(&result)?;
+				Ok(submitted(
+					&result,
+					json!({
+						"who": event.who.to_string(),
+						"proposal_id": event.proposal_id,
+						"currency_id": event.currency_id,
+					}),
+				))
+			}
+			CoupleCommand::Buy {
+				proposal_id,
+				currency_id,
+				number,
+				min_output,
+				max_price,
+			} => {
+				let signer = ctx.signer()?;
+				let received = match limit(min_output, max_price) {
+					Some(limit) => {
+						XPredictLogic::buy_with_limit(
+							client,
+							&signer,
+							proposal_id,
+							currency_id,
+							number,
+							limit,
+						)
+						.await?
+					}
+					None => {
+						XPredictLogic::proposal_buy(
+							client,
+							&signer,
+							proposal_id,
+							currency_id,
+							number,
+						)
+						.await?
+					}
+				};
+				Ok(trade(proposal_id, currency_id, number, received))
+			}
+			CoupleCommand::Sell {
+				proposal_id,
+				currency_id,
+				number,
+				min_output,
+				min_price,
+			} => {
+				let signer = ctx.signer()?;
+				let received = match limit(min_output, min_price) {
+					Some(limit) => {
+						XPredictLogic::sell_with_limit(
+							client,
+							&signer,
+							proposal_id,
+							currency_id,
+							number,
+							limit,
+						)
+						.await?
+					}
+					None => {
+						XPredictLogic::proposal_sell(
+							client,
+							&signer,
+							proposal_id,
+							currency_id,
+							number,
+						)
+						.await?
+					}
+				};
+				Ok(trade(proposal_id, currency_id, number, received))
+			}
+			CoupleCommand::AddLiquidity {
+				proposal_id,
+				number,
+			} => {
+				let added = XPredictLogic::proposal_add_liquidity(
+					client,
+					&ctx.signer()?,
+					proposal_id,
+					number,
+				)
+				.await?;
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"number": added.to_string(),
+				}))
+			}
+			CoupleCommand::RemoveLiquidity {
+				proposal_id,
+				number,
+			} => {
+				let removed = XPredictLogic::proposal_remove_liquidity(
+					client,
+					&ctx.signer()?,
+					proposal_id,
+					number,
+				)
+				.await?;
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"number": removed.to_string(),
+				}))
+			}
+			CoupleCommand::Retrieval {
+				proposal_id,
+				currency_id,
+				number,
+			} => {
+				let received = XPredictLogic::proposal_retrieval(
+					client,
+					&ctx.signer()?,
+					proposal_id,
+					currency_id,
+					number,
+				)
+				.await?;
+				Ok(trade(proposal_id, currency_id, number, received))
+			}
+			CoupleCommand::WithdrawalReward { proposal_id } => {
+				let reward =
+					XPredictLogic::proposal_withdrawal_reward(client, &ctx.signer()?, proposal_id)
+						.await?;
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"reward": reward.to_string(),
+				}))
+			}
+			CoupleCommand::SetResult {
+				proposal_id,
+				currency_id,
+			} => {
+				let currency_id = XPredictLogic::proposal_set_result(
+					client,
+					&ctx.signer()?,
+					proposal_id,
+					currency_id,
+				)
+				.await?;
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"currency_id": currency_id,
+				}))
+			}
+			CoupleCommand::Info { proposal_id } => {
+				let (yes, no) = XPredictLogic::proposal_pairs(client, proposal_id).await?;
+				let (yes_number, no_number) =
+					XPredictLogic::proposal_optional(client, proposal_id).await?;
+				let status = XPredictLogic::proposal_status(client, proposal_id).await?;
+				let close_time = XPredictLogic::proposal_close_time(client, proposal_id).await?;
+				let pool = PoolState::fetch(client, proposal_id).await?;
+				let (yes_probability, no_probability) = pool.probabilities();
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"status": format!("{:?}", status),
+					"close_time": close_time,
+					"yes": {
+						"currency_id": yes,
+						"market": yes_number.to_string(),
+						"probability": yes_probability,
+					},
+					"no": {
+						"currency_id": no,
+						"market": no_number.to_string(),
+						"probability": no_probability,
+					},
+				}))
+			}
+			CoupleCommand::Quote(command) => command.run(ctx).await,
+		}
+	}
+}
+
+impl QuoteCommand {
+	async fn run(self, ctx: &Context) -> Result<Value> {
+		let client = &ctx.client;
+		match self {
+			QuoteCommand::Buy {
+				proposal_id,
+				currency_id,
+				number,
+			} => {
+				let pool = PoolState::fetch(client, proposal_id).await?;
+				Ok(trade_quote(&pool.quote_buy(currency_id, number)?))
+			}
+			QuoteCommand::Sell {
+				proposal_id,
+				currency_id,
+				number,
+			} => {
+				let pool = PoolState::fetch(client, proposal_id).await?;
+				Ok(trade_quote(&pool.quote_sell(currency_id, number)?))
+			}
+			QuoteCommand::AddLiquidity {
+				proposal_id,
+				number,
+			} => {
+				let pool = PoolState::fetch(client, proposal_id).await?;
+				Ok(liquidity_quote(&pool.quote_add_liquidity(number)?))
+			}
+			QuoteCommand::RemoveLiquidity {
+				proposal_id,
+				number,
+			} => {
+				let pool = PoolState::fetch(client, proposal_id).await?;
+				Ok(liquidity_quote(&pool.quote_remove_liquidity(number)?))
+			}
+		}
+	}
+}
+
+fn trade_quote(quote: &TradeQuote) -> Value {
+	json!({
+		"currency_id": quote.currency_id,
+		"input": quote.input.to_string(),
+		"output": quote.output.to_string(),
+		"owner_fee": quote.owner_fee.to_string(),
+		"liquidity_fee": quote.liquidity_fee.to_string(),
+		"price_before": quote.price_before,
+		"price_after": quote.price_after,
+		"average_price": quote.average_price,
+		"price_impact": quote.price_impact,
+	})
+}
+
+fn liquidity_quote(quote: &LiquidityQuote) -> Value {
+	json!({
+		"shares": quote.shares.to_string(),
+		"yes": quote.yes.to_string(),
+		"no": quote.no.to_string(),
+		"fee_reward": quote.fee_reward.to_string(),
+	})
+}
+
+fn limit(min_output: Option<Balance>, price_bound: Option<f64>) -> Option<TradeLimit> {
+	if min_output.is_none() && price_bound.is_none() {
+		return None;
+	}
+	Some(TradeLimit {
+		min_output: min_output.unwrap_or(0),
+		price_bound,
+	})
+}
+
+fn trade(
+	proposal_id: ProposalId,
+	currency_id: CurrencyId,
+	number: Balance,
+	received: Balance,
+) -> Value {
+	json!({
+		"proposal_id": proposal_id,
+		"currency_id": currency_id,
+		"number": number.to_string(),
+		"received": received.to_string(),
+	})
+}
@@ -0,0 +1,42 @@
+use futures::{pin_mut, StreamExt};
+use serde_json::json;
+use structopt::StructOpt;
+
+use super::{Context, OutputFormat};
+use ws_client::{error::Result, events::subscribe_events};
+
+#[derive(Debug, StructOpt)]
+pub struct EventsCommand {
+	/// Follow finalized blocks only instead of the best chain
+	#[structopt(long)]
+	finalized: bool,
+	/// Only print events of this pallet, e.g. `Couple`
+	#[structopt(long)]
+	module: Option<String>,
+}
+
+impl EventsCommand {
+	/// Prints every event until the subscription ends, one JSON line each
+	/// with `--output json`. Events that cannot be read are printed as
+	/// errors and skipped.
+	pub async fn run(self, ctx: &Context, output: OutputFormat) -> Result<()> {
+		let events = subscribe_events(&ctx.client, self.finalized).await?;
+		pin_mut!(events);
+		while let Some(record) = events.next().await {
+			let record = match record {
+				Ok(record) => record,
+				Err(e) => {
+					output.print(&json!({ "error": e.to_string() }));
+					continue;
+				}
+			};
+			if let Some(module) = &self.module {
+				if !record.event.module().eq_ignore_ascii_case(module) {
+					continue;
+				}
+			}
+			output.print(&record.to_json());
+		}
+		Ok(())
+	}
+}
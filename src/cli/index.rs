@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+use super::Context;
+use ws_client::{
+	error::Result,
+	indexer::{IndexDb, Indexer},
+	runtime::BlockNumber,
+};
+
+#[derive(Debug, StructOpt)]
+pub struct IndexCommand {
+	/// SQLite database, defaults to `indexer.database` of the config
+	#[structopt(long, parse(from_os_str))]
+	database: Option<PathBuf>,
+	/// First block of an empty database, defaults to `indexer.start_block`
+	#[structopt(long)]
+	start_block: Option<BlockNumber>,
+	/// Stop once the current best block is indexed instead of following the chain
+	#[structopt(long)]
+	once: bool,
+}
+
+impl IndexCommand {
+	pub async fn run(self, ctx: &Context) -> Result<Value> {
+		let config = &ctx.config.indexer;
+		let database = self.database.unwrap_or_else(|| config.database.clone());
+		let db = IndexDb::open(&database)?;
+		let mut indexer = Indexer::new(
+			&ctx.client,
+			db,
+			self.start_block.unwrap_or(config.start_block),
+		);
+		if self.once {
+			let best = indexer.sync().await?;
+			return Ok(json!({
+				"database": database.display().to_string(),
+				"best_block": best,
+			}));
+		}
+		indexer.run().await?;
+		Ok(json!({ "database": database.display().to_string() }))
+	}
+}
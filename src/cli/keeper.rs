@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use serde_json::Value;
+use structopt::StructOpt;
+
+use super::{Context, OutputFormat};
+use ws_client::{
+	error::Result,
+	keeper::{Journal, JournalEntry, Keeper},
+};
+
+#[derive(Debug, StructOpt)]
+pub enum KeeperCommand {
+	/// Drive overdue status transitions with the `--signer` sudo key
+	Run {
+		/// Journal what would be done without submitting anything
+		#[structopt(long)]
+		dry_run: bool,
+		/// Defaults to `keeper.journal` of the config
+		#[structopt(long, parse(from_os_str))]
+		journal: Option<PathBuf>,
+		/// Stop after one pass instead of following the chain
+		#[structopt(long)]
+		once: bool,
+	},
+	/// Print the journal
+	Journal {
+		#[structopt(long, parse(from_os_str))]
+		journal: Option<PathBuf>,
+	},
+}
+
+impl KeeperCommand {
+	/// Prints each journal entry, as it is written when running.
+	pub async fn run(self, ctx: &Context, output: OutputFormat) -> Result<()> {
+		let config = &ctx.config.keeper;
+		match self {
+			KeeperCommand::Run {
+				dry_run,
+				journal,
+				once,
+			} => {
+				let journal = Journal::open(&journal.unwrap_or_else(|| config.journal.clone()))?;
+				let signer = ctx.signer()?;
+				let mut keeper =
+					Keeper::new(&ctx.client, &*signer, config.clone(), journal, dry_run)?;
+				let mut print = |entry: &JournalEntry| output.print(&to_json(entry));
+				if once {
+					return keeper.tick(&mut print).await;
+				}
+				keeper.run(print).await
+			}
+			KeeperCommand::Journal { journal } => {
+				let journal = Journal::open(&journal.unwrap_or_else(|| config.journal.clone()))?;
+				for entry in journal.entries()? {
+					output.print(&to_json(&entry));
+				}
+				Ok(())
+			}
+		}
+	}
+}
+
+fn to_json(entry: &JournalEntry) -> Value {
+	serde_json::to_value(entry).unwrap_or_default()
+}
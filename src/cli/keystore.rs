@@ -0,0 +1,96 @@
+use std::{env, path::Path};
+
+use serde_json::{json, Value};
+use structopt::StructOpt;
+use subxt::sp_core::{ecdsa, ed25519, sr25519};
+use zeroize::Zeroizing;
+
+use super::parse_authority;
+use ws_client::{
+	config::XPredictConfig,
+	error::{Result, XPredictError},
+	keystore::{EncryptedKeystore, KeyScheme, KeySource, SchemePair},
+	pairs::PairAuthority,
+};
+
+#[derive(Debug, StructOpt)]
+pub enum KeystoreCommand {
+	/// Encrypt a key into the keystore configured as `keys.keystore`
+	Add {
+		#[structopt(parse(try_from_str = parse_authority))]
+		authority: PairAuthority,
+		/// Secret URI, mnemonic or 0x seed, prompted for when omitted
+		uri: Option<String>,
+	},
+	/// List the stored addresses without unlocking them
+	List,
+}
+
+impl KeystoreCommand {
+	pub fn run(self, config: &XPredictConfig, scheme: KeyScheme) -> Result<Value> {
+		let path = config
+			.keys
+			.keystore
+			.as_ref()
+			.ok_or_else(|| XPredictError::Config("keys.keystore is not set".into()))?;
+		match self {
+			KeystoreCommand::Add { authority, uri } => {
+				let uri = match uri {
+					Some(uri) => Zeroizing::new(uri),
+					None => prompt("Secret URI: ")?,
+				};
+				let source = KeySource::Uri(uri.to_string());
+				let password = match env::var(&config.keys.keystore_password_env) {
+					Ok(password) => Zeroizing::new(password),
+					Err(_) => {
+						let password = prompt("Keystore password: ")?;
+						if *password != *prompt("Repeat password: ")? {
+							return Err(XPredictError::Keystore("passwords do not match".into()));
+						}
+						password
+					}
+				};
+				match scheme {
+					KeyScheme::Sr25519 => add::<sr25519::Pair>(path, authority, &source, &password),
+					KeyScheme::Ed25519 => add::<ed25519::Pair>(path, authority, &source, &password),
+					KeyScheme::Ecdsa => add::<ecdsa::Pair>(path, authority, &source, &password),
+				}
+			}
+			KeystoreCommand::List => {
+				let accounts = EncryptedKeystore::<sr25519::Pair>::open(path)?
+					.list()?
+					.into_iter()
+					.map(|(authority, address, scheme)| {
+						json!({
+							"authority": format!("{:?}", authority),
+							"address": address,
+							"scheme": scheme,
+						})
+					})
+					.collect::<Vec<_>>();
+				Ok(json!({ "accounts": accounts }))
+			}
+		}
+	}
+}
+
+fn add<P: SchemePair>(
+	path: &Path,
+	authority: PairAuthority,
+	source: &KeySource,
+	password: &str,
+) -> Result<Value> {
+	let pair = source.load::<P>()?;
+	let path = EncryptedKeystore::<P>::open(path)?.add(authority, &pair, password)?;
+	Ok(json!({
+		"address": pair.address(),
+		"scheme": P::SCHEME.name(),
+		"path": path.display().to_string(),
+	}))
+}
+
+fn prompt(message: &str) -> Result<Zeroizing<String>> {
+	rpassword::read_password_from_tty(Some(message))
+		.map(Zeroizing::new)
+		.map_err(|e| XPredictError::Keystore(e.to_string()))
+}
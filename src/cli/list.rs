@@ -0,0 +1,116 @@
+use std::future::Future;
+
+use serde_json::{json, Value};
+use structopt::StructOpt;
+use subxt::sp_core::storage::StorageKey;
+
+use super::Context;
+use ws_client::{
+	error::Result,
+	listing::{
+		all_pages, currencies_page, holders_page, proposals_page, stakers_page, uploaders_page,
+		AccountRow, CurrencyRow, HolderRow, Page, ProposalRow,
+	},
+	pallets::{CurrencyId, ProposalStatus},
+};
+
+#[derive(Debug, StructOpt)]
+pub struct ListCommand {
+	#[structopt(flatten)]
+	page: PageOpts,
+	#[structopt(subcommand)]
+	command: ListSubcommand,
+}
+
+#[derive(Debug, StructOpt)]
+struct PageOpts {
+	/// Storage keys fetched per page
+	#[structopt(long, default_value = "100")]
+	page_size: u32,
+	/// Key to continue after, the `next` of the previous page
+	#[structopt(long, parse(try_from_str = parse_key))]
+	start: Option<StorageKey>,
+	/// Follow every page instead of printing one
+	#[structopt(long)]
+	all: bool,
+}
+
+#[derive(Debug, StructOpt)]
+enum ListSubcommand {
+	/// Proposals by id with their status
+	Proposals {
+		/// Only proposals open for trading
+		#[structopt(long)]
+		open: bool,
+	},
+	/// Currencies created with the tokens pallet
+	Currencies,
+	/// Free balances of every account
+	Holders {
+		#[structopt(long)]
+		currency_id: Option<CurrencyId>,
+	},
+	/// Accounts with an autonomy stake
+	Stakers,
+	/// Tagged accounts allowed to upload results
+	Uploaders,
+}
+
+fn parse_key(s: &str) -> std::result::Result<StorageKey, hex::FromHexError> {
+	hex::decode(s.trim_start_matches("0x")).map(StorageKey)
+}
+
+impl ListCommand {
+	pub async fn run(self, ctx: &Context) -> Result<Value> {
+		let client = &ctx.client;
+		let size = self.page.page_size;
+		match self.command {
+			ListSubcommand::Proposals { open } => {
+				let keep =
+					|row: &ProposalRow| !open || row.status == ProposalStatus::FormalPrediction;
+				let page = |start| proposals_page(client, size, start);
+				list(&self.page, page, keep, ProposalRow::to_json).await
+			}
+			ListSubcommand::Currencies => {
+				let page = |start| currencies_page(client, size, start);
+				list(&self.page, page, |_| true, CurrencyRow::to_json).await
+			}
+			ListSubcommand::Holders { currency_id } => {
+				let keep = |row: &HolderRow| currency_id.map_or(true, |id| row.currency_id == id);
+				let page = |start| holders_page(client, size, start);
+				list(&self.page, page, keep, HolderRow::to_json).await
+			}
+			ListSubcommand::Stakers => {
+				let page = |start| stakers_page(client, size, start);
+				list(&self.page, page, |_| true, AccountRow::to_json).await
+			}
+			ListSubcommand::Uploaders => {
+				let page = |start| uploaders_page(client, size, start);
+				list(&self.page, page, |_| true, AccountRow::to_json).await
+			}
+		}
+	}
+}
+
+/// One page with the key to continue from, or every row with `--all`.
+async fn list<T, F, Fut>(
+	opts: &PageOpts,
+	mut page: F,
+	keep: impl Fn(&T) -> bool,
+	to_json: fn(&T) -> Value,
+) -> Result<Value>
+where
+	F: FnMut(Option<StorageKey>) -> Fut,
+	Fut: Future<Output = Result<Page<T>>>,
+{
+	let rows_json =
+		|rows: Vec<T>| -> Vec<Value> { rows.iter().filter(|row| keep(row)).map(to_json).collect() };
+	if opts.all {
+		return Ok(Value::Array(rows_json(all_pages(page).await?)));
+	}
+	let Page { rows, next } = page(opts.start.clone()).await?;
+	Ok(json!({
+		"rows": rows_json(rows),
+		"next": next.map(|key| format!("0x{}", hex::encode(&key.0))),
+	}))
+}
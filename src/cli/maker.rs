@@ -0,0 +1,45 @@
+use serde_json::Value;
+use structopt::StructOpt;
+
+use super::{Context, OutputFormat};
+use ws_client::{
+	error::Result,
+	maker::{Maker, MakerEvent},
+};
+
+#[derive(Debug, StructOpt)]
+pub enum MakerCommand {
+	/// Provide liquidity with the `--signer` key as set in `[maker]`
+	Run {
+		/// Stop after one pass instead of following the chain
+		#[structopt(long)]
+		once: bool,
+	},
+	/// Show the inventory and PnL of every position, marked to the pools
+	Positions,
+}
+
+impl MakerCommand {
+	pub async fn run(self, ctx: &Context, output: OutputFormat) -> Result<()> {
+		let signer = ctx.signer()?;
+		let mut maker = Maker::new(&ctx.client, &*signer, ctx.config.maker.clone())?;
+		match self {
+			MakerCommand::Run { once } => {
+				let mut print = |event: &MakerEvent| output.print(&event.to_json());
+				if once {
+					return maker.tick(&mut print).await;
+				}
+				maker.run(print).await
+			}
+			MakerCommand::Positions => {
+				let mut positions = Vec::new();
+				for (proposal_id, position) in maker.positions() {
+					let inventory = maker.inventory(*proposal_id).await?;
+					positions.push(inventory.to_json(*proposal_id, position));
+				}
+				output.print(&Value::Array(positions));
+				Ok(())
+			}
+		}
+	}
+}
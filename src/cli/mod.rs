@@ -0,0 +1,228 @@
+mod autonomy;
+mod claim;
+mod couple;
+mod events;
+mod index;
+mod keeper;
+mod keystore;
+mod list;
+mod maker;
+mod oracle;
+mod output;
+mod portfolio;
+mod proposals;
+mod ruler;
+mod scenario;
+mod tokens;
+
+use std::{path::PathBuf, str::FromStr};
+
+use structopt::StructOpt;
+use subxt::{
+	sp_core::{ecdsa, ed25519},
+	Client, ClientBuilder, PairSigner, Signer,
+};
+
+use ws_client::{
+	config::{ConfigOverrides, XPredictConfig},
+	error::{Result, XPredictError},
+	keystore::{KeyScheme, SchemePair},
+	pairs::{PairAuthority, XPredictKeystore, XPredictSigner},
+	runtime::XPredictRuntime,
+};
+
+pub use output::OutputFormat;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "ws-client")]
+pub struct Opt {
+	/// TOML settings file, defaults to ./xpredict.toml when present
+	#[structopt(short, long, parse(from_os_str))]
+	config: Option<PathBuf>,
+	/// Node websocket endpoint
+	#[structopt(long)]
+	url: Option<String>,
+	/// Seconds to wait for a proposal status before giving up
+	#[structopt(long)]
+	status_wait_secs: Option<u64>,
+	/// Earn fee of new proposals
+	#[structopt(long)]
+	earn_fee: Option<u32>,
+	/// Seconds from now until new proposals close
+	#[structopt(long)]
+	close_after_secs: Option<u64>,
+	/// Output format, `human` or `json`
+	#[structopt(long, default_value = "human")]
+	output: OutputFormat,
+	/// Key signing extrinsics, as `admin[:index]` or `normal[:index]`
+	#[structopt(long, default_value = "admin")]
+	signer: SignerRef,
+	/// Key scheme of the signer, `sr25519`, `ed25519` or `ecdsa`
+	#[structopt(long, default_value = "sr25519")]
+	scheme: KeyScheme,
+	#[structopt(subcommand)]
+	command: Command,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+	/// Calls and storage of the tokens pallet
+	Tokens(tokens::TokensCommand),
+	/// Calls and storage of the couple pallet
+	Couple(couple::CoupleCommand),
+	/// Calls and storage of the proposals pallet
+	Proposals(proposals::ProposalsCommand),
+	/// Calls and storage of the autonomy pallet
+	Autonomy(autonomy::AutonomyCommand),
+	/// Calls of the ruler pallet
+	Ruler(ruler::RulerCommand),
+	/// Claim every reward, stake and winning of an account in batches
+	Claim(claim::ClaimCommand),
+	/// Tail the events of the XPredict pallets
+	Events(events::EventsCommand),
+	/// Index proposals, trades, transfers and autonomy actions into SQLite
+	Index(index::IndexCommand),
+	/// Page through proposals, currencies, holders and autonomy accounts
+	List(list::ListCommand),
+	/// Holdings of an account across currencies and proposals, marked to market
+	Portfolio(portfolio::PortfolioCommand),
+	/// Make the proposal status transitions the chain has not made on time
+	Keeper(keeper::KeeperCommand),
+	/// Manage the encrypted keystore
+	Keystore(keystore::KeystoreCommand),
+	/// Provide liquidity to Couple pools
+	Maker(maker::MakerCommand),
+	/// Upload the results of proposals waiting for them
+	Oracle(oracle::OracleCommand),
+	/// Run a scenario file step by step
+	Scenario(scenario::ScenarioCommand),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SignerRef {
+	authority: PairAuthority,
+	index: usize,
+}
+
+impl FromStr for SignerRef {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		let (authority, index) = match s.split_once(':') {
+			Some((authority, index)) => (
+				authority,
+				index
+					.parse()
+					.map_err(|_| format!("invalid key index {:?}", index))?,
+			),
+			None => (s, 0),
+		};
+		Ok(SignerRef {
+			authority: parse_authority(authority)?,
+			index,
+		})
+	}
+}
+
+pub fn parse_authority(s: &str) -> std::result::Result<PairAuthority, String> {
+	match s {
+		"admin" => Ok(PairAuthority::Admin),
+		"normal" => Ok(PairAuthority::Normal),
+		_ => Err(format!("unknown authority {:?}, use admin or normal", s)),
+	}
+}
+
+pub struct Context {
+	pub client: Client<XPredictRuntime>,
+	pub keystore: XPredictKeystore,
+	pub ed25519: XPredictKeystore<ed25519::Pair>,
+	pub ecdsa: XPredictKeystore<ecdsa::Pair>,
+	pub config: XPredictConfig,
+	pub scheme: KeyScheme,
+	signer: SignerRef,
+}
+
+impl Context {
+	/// The `--signer` key of the `--scheme` keystore.
+	pub fn signer(&self) -> Result<Box<XPredictSigner>> {
+		match self.scheme {
+			KeyScheme::Sr25519 => self.pair(&self.keystore).map(boxed_signer),
+			KeyScheme::Ed25519 => self.pair(&self.ed25519).map(boxed_signer),
+			KeyScheme::Ecdsa => self.pair(&self.ecdsa).map(boxed_signer),
+		}
+	}
+
+	pub fn pair<'a, P: SchemePair>(&self, keystore: &'a XPredictKeystore<P>) -> Result<&'a P> {
+		keystore
+			.get_pair(self.signer.authority, self.signer.index)
+			.ok_or_else(|| {
+				XPredictError::Config(format!(
+					"no {} {:?} key with index {}",
+					P::SCHEME.name(),
+					self.signer.authority,
+					self.signer.index
+				))
+			})
+	}
+}
+
+fn boxed_signer<P>(pair: &P) -> Box<XPredictSigner>
+where
+	P: SchemePair,
+	PairSigner<XPredictRuntime, P>: Signer<XPredictRuntime> + Send + Sync + 'static,
+{
+	Box::new(PairSigner::new(pair.clone()))
+}
+
+impl Opt {
+	fn load_config(&self) -> Result<XPredictConfig> {
+		let overrides = ConfigOverrides {
+			endpoint: self.url.clone(),
+			status_wait_secs: self.status_wait_secs,
+			earn_fee: self.earn_fee,
+			close_after_secs: self.close_after_secs,
+		};
+		XPredictConfig::load(self.config.as_deref(), &overrides)
+	}
+
+	pub async fn run(self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+		let config = self.load_config()?;
+		if let Command::Keystore(command) = self.command {
+			self.output.print(&command.run(&config, self.scheme)?);
+			return Ok(());
+		}
+		let client = ClientBuilder::<XPredictRuntime>::new()
+			.set_url(config.endpoint.clone())
+			.build()
+			.await?;
+		let ctx = Context {
+			client,
+			keystore: XPredictKeystore::from_config(&config.keys)?,
+			ed25519: XPredictKeystore::from_config(&config.keys)?,
+			ecdsa: XPredictKeystore::from_config(&config.keys)?,
+			config,
+			scheme: self.scheme,
+			signer: self.signer,
+		};
+
+		let value = match self.command {
+			Command::Tokens(command) => command.run(&ctx).await?,
+			Command::Couple(command) => command.run(&ctx).await?,
+			Command::Proposals(command) => command.run(&ctx).await?,
+			Command::Autonomy(command) => command.run(&ctx).await?,
+			Command::Ruler(command) => command.run(&ctx).await?,
+			Command::Index(command) => command.run(&ctx).await?,
+			Command::List(command) => command.run(&ctx).await?,
+			Command::Portfolio(command) => command.run(&ctx).await?,
+			Command::Claim(command) => return Ok(command.run(&ctx, self.output).await?),
+			Command::Events(command) => return Ok(command.run(&ctx, self.output).await?),
+			Command::Keeper(command) => return Ok(command.run(&ctx, self.output).await?),
+			Command::Maker(command) => return Ok(command.run(&ctx, self.output).await?),
+			Command::Oracle(command) => return Ok(command.run(&ctx, self.output).await?),
+			Command::Scenario(command) => return Ok(command.run(&ctx, self.output).await?),
+			Command::Keystore(_) => unreachable!("handled before connecting"),
+		};
+		self.output.print(&value);
+		Ok(())
+	}
+}
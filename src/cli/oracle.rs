@@ -0,0 +1,85 @@
+use std::{path::PathBuf, time::Duration};
+
+use structopt::StructOpt;
+use subxt::sp_runtime::{traits::Verify, MultiSignature};
+
+use super::{Context, OutputFormat, SignerRef};
+use ws_client::{
+	error::{Result, XPredictError},
+	keystore::{KeyScheme, SchemePair},
+	oracle::{CommandResolver, FileResolver, Oracle, Resolver},
+	pairs::XPredictKeystore,
+};
+
+#[derive(Debug, StructOpt)]
+pub struct OracleCommand {
+	/// JSON or CSV file of answers, re-read on every lookup
+	#[structopt(long, parse(from_os_str), required_unless = "command")]
+	file: Option<PathBuf>,
+	/// Command printing `yes`, `no` or nothing for the proposal id appended
+	/// to it, quoted like a shell command
+	#[structopt(long, conflicts_with = "file")]
+	command: Option<String>,
+	/// Seconds the command may run before it is killed
+	#[structopt(long, default_value = "30")]
+	command_timeout_secs: u64,
+	/// Tagged autonomy accounts of the `--scheme` keys to upload for,
+	/// defaults to `--signer`
+	#[structopt(long = "account")]
+	accounts: Vec<SignerRef>,
+}
+
+impl OracleCommand {
+	/// Prints every decision until the block subscription ends.
+	pub async fn run(self, ctx: &Context, output: OutputFormat) -> Result<()> {
+		let resolver: Box<dyn Resolver> = match (&self.file, &self.command) {
+			(Some(path), _) => Box::new(FileResolver::new(path)),
+			(None, Some(command)) => Box::new(CommandResolver::parse(
+				command,
+				Duration::from_secs(self.command_timeout_secs),
+			)?),
+			(None, None) => unreachable!("structopt requires --file or --command"),
+		};
+		match ctx.scheme {
+			KeyScheme::Sr25519 => self.serve(ctx, &ctx.keystore, resolver, output).await,
+			KeyScheme::Ed25519 => self.serve(ctx, &ctx.ed25519, resolver, output).await,
+			KeyScheme::Ecdsa => self.serve(ctx, &ctx.ecdsa, resolver, output).await,
+		}
+	}
+
+	async fn serve<P>(
+		&self,
+		ctx: &Context,
+		keystore: &XPredictKeystore<P>,
+		resolver: Box<dyn Resolver>,
+		output: OutputFormat,
+	) -> Result<()>
+	where
+		P: SchemePair,
+		MultiSignature: From<P::Signature>,
+		<MultiSignature as Verify>::Signer: From<P::Public>,
+	{
+		let pairs = if self.accounts.is_empty() {
+			vec![ctx.pair(keystore)?]
+		} else {
+			self.accounts
+				.iter()
+				.map(|account| {
+					keystore
+						.get_pair(account.authority, account.index)
+						.ok_or_else(|| {
+							XPredictError::Config(format!(
+								"no {} {:?} key with index {}",
+								P::SCHEME.name(),
+								account.authority,
+								account.index
+							))
+						})
+				})
+				.collect::<Result<_>>()?
+		};
+		Oracle::new(&ctx.client, resolver, pairs)
+			.run(|decision| output.print(&decision.to_json()))
+			.await
+	}
+}
@@ -0,0 +1,76 @@
+use std::str::FromStr;
+
+use serde_json::Value;
+use subxt::ExtrinsicSuccess;
+
+use ws_client::runtime::XPredictRuntime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+	Human,
+	Json,
+}
+
+impl FromStr for OutputFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"human" => Ok(OutputFormat::Human),
+			"json" => Ok(OutputFormat::Json),
+			_ => Err(format!("unknown output format {:?}, use human or json", s)),
+		}
+	}
+}
+
+impl OutputFormat {
+	pub fn print(self, value: &Value) {
+		match self {
+			OutputFormat::Json => println!("{}", value),
+			OutputFormat::Human => print_human(value, 0),
+		}
+	}
+}
+
+fn print_human(value: &Value, indent: usize) {
+	match value {
+		Value::Object(map) => {
+			for (key, value) in map {
+				match value {
+					Value::Object(_) | Value::Array(_) => {
+						println!("{:indent$}{}:", "", key, indent = indent);
+						print_human(value, indent + 2);
+					}
+					_ => println!("{:indent$}{}: {}", "", key, scalar(value), indent = indent),
+				}
+			}
+		}
+		Value::Array(items) => {
+			for item in items {
+				match item {
+					Value::Object(_) | Value::Array(_) => {
+						println!("{:indent$}-", "", indent = indent);
+						print_human(item, indent + 2);
+					}
+					_ => println!("{:indent$}- {}", "", scalar(item), indent = indent),
+				}
+			}
+		}
+		_ => println!("{:indent$}{}", "", scalar(value), indent = indent),
+	}
+}
+
+fn scalar(value: &Value) -> String {
+	match value {
+		Value::String(s) => s.clone(),
+		value => value.to_string(),
+	}
+}
+
+/// Tags the output of a submitted extrinsic with the block it was included in.
+pub fn submitted(result: &ExtrinsicSuccess<XPredictRuntime>, mut value: Value) -> Value {
+	if let Value::Object(map) = &mut value {
+		map.insert("block".into(), format!("{:?}", result.block).into());
+	}
+	value
+}
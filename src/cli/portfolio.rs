@@ -0,0 +1,21 @@
+use serde_json::Value;
+use structopt::StructOpt;
+
+use super::Context;
+use ws_client::{error::Result, portfolio::Portfolio, runtime::AccountId};
+
+#[derive(Debug, StructOpt)]
+pub struct PortfolioCommand {
+	/// Defaults to the `--signer` account
+	account: Option<AccountId>,
+}
+
+impl PortfolioCommand {
+	pub async fn run(self, ctx: &Context) -> Result<Value> {
+		let account = match self.account {
+			Some(account) => account,
+			None => ctx.signer()?.account_id().clone(),
+		};
+		Ok(Portfolio::fetch(&ctx.client, &account).await?.to_json())
+	}
+}
@@ -0,0 +1,233 @@
+use std::{marker::PhantomData, str::FromStr, time::Duration};
+
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+use super::{output::submitted, Context};
+use ws_client::{
+	error::Result,
+	logic::{expect_event, sudo, XPredictLogic},
+	pallets::{proposals::*, Balance, ProposalId, ProposalStatus},
+	runtime::AccountId,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opinion {
+	Yes,
+	No,
+}
+
+impl FromStr for Opinion {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s {
+			"yes" => Ok(Opinion::Yes),
+			"no" => Ok(Opinion::No),
+			_ => Err(format!("unknown opinion {:?}, use yes or no", s)),
+		}
+	}
+}
+
+impl From<Opinion> for bool {
+	fn from(opinion: Opinion) -> bool {
+		opinion == Opinion::Yes
+	}
+}
+
+pub fn parse_status(s: &str) -> std::result::Result<ProposalStatus, String> {
+	let status = match s.replace(&['-', '_'][..], "").to_lowercase().as_str() {
+		"formalprediction" => ProposalStatus::FormalPrediction,
+		"originalprediction" => ProposalStatus::OriginalPrediction,
+		"waitingforresults" => ProposalStatus::WaitingForResults,
+		"resultannouncement" => ProposalStatus::ResultAnnouncement,
+		"inlitigation" => ProposalStatus::Inlitigation,
+		"end" => ProposalStatus::End,
+		_ => return Err(format!("unknown proposal status {:?}", s)),
+	};
+	Ok(status)
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ProposalsCommand {
+	/// Force the status of a proposal (sudo)
+	SetStatus {
+		proposal_id: ProposalId,
+		#[structopt(parse(try_from_str = parse_status))]
+		status: ProposalStatus,
+	},
+	/// Stake for (`yes`) or against (`no`) an original prediction
+	StakeTo {
+		proposal_id: ProposalId,
+		number: Balance,
+		opinion: Opinion,
+	},
+	/// Take back the stake on a proposal
+	UnstakeFrom { proposal_id: ProposalId },
+	/// Withdraw the voting reward of a proposal
+	WithdrawalReward { proposal_id: ProposalId },
+	/// Deposit tokens into the proposal reward pool
+	DepositReward { number: Balance },
+	/// Reclaim the proposal reward pool to an account
+	ReclaimReward { to: AccountId },
+	/// Show the vote tallies against `MinimumVote`, and the vote of an account
+	Votes {
+		proposal_id: ProposalId,
+		#[structopt(long)]
+		account: Option<AccountId>,
+	},
+	/// Show the status of a proposal
+	Status { proposal_id: ProposalId },
+	/// Wait until a proposal reaches a status
+	Wait {
+		proposal_id: ProposalId,
+		#[structopt(parse(try_from_str = parse_status))]
+		status: ProposalStatus,
+		/// Defaults to `timeouts.status_wait_secs` of the config
+		#[structopt(long)]
+		timeout_secs: Option<u64>,
+	},
+}
+
+impl ProposalsCommand {
+	pub async fn run(self, ctx: &Context) -> Result<Value> {
+		let client = &ctx.client;
+		match self {
+			ProposalsCommand::SetStatus {
+				proposal_id,
+				status,
+			} => {
+				let call = client.encode(SetStatusCall {
+					proposal_id,
+					new_status: status,
+					_runtime: PhantomData,
+				})?;
+				let result = sudo(client, &ctx.signer()?, &call).await?;
+				let event = expect_event::<ProposalStatusChangedEvent<_>>(&result)?;
+				Ok(submitted(
+					&result,
+					json!({
+						"proposal_id": event.proposal_id,
+						"status": format!("{:?}", event.state),
+					}),
+				))
+			}
+			ProposalsCommand::StakeTo {
+				proposal_id,
+				number,
+				opinion,
+			} => {
+				let number = XPredictLogic::proposal_stake_to(
+					client,
+					&ctx.signer()?,
+					proposal_id,
+					number,
+					opinion.into(),
+				)
+				.await?;
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"number": number.to_string(),
+					"opinion": bool::from(opinion),
+				}))
+			}
+			ProposalsCommand::UnstakeFrom { proposal_id } => {
+				let number =
+					XPredictLogic::proposal_unstake_from(client, &ctx.signer()?, proposal_id)
+						.await?;
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"number": number.to_string(),
+				}))
+			}
+			ProposalsCommand::WithdrawalReward { proposal_id } => {
+				let result = client
+					.withdrawal_reward_and_watch(&ctx.signer()?, proposal_id)
+					.await?;
+				let event = expect_event::<WithdrawalRewardEvent<_>>(&result)?;
+				Ok(submitted(
+					&result,
+					json!({
+						"who": event.who.to_string(),
+						"proposal_id": event.proposal_id,
+						"number": event.number.to_string(),
+					}),
+				))
+			}
+			ProposalsCommand::DepositReward { number } => {
+				let result = client
+					.deposit_reward_and_watch(&ctx.signer()?, number)
+					.await?;
+				let event = expect_event::<DepositRewardEvent<_>>(&result)?;
+				Ok(submitted(
+					&result,
+					json!({
+						"from": event.from.to_string(),
+						"to": event.to.to_string(),
+						"number": event.number.to_string(),
+					}),
+				))
+			}
+			ProposalsCommand::ReclaimReward { to } => {
+				let result = client.reclaim_reward_and_watch(&ctx.signer()?, &to).await?;
+				let event = expect_event::<ReclaimRewardEvent<_>>(&result)?;
+				Ok(submitted(
+					&result,
+					json!({
+						"from": event.from.to_string(),
+						"to": event.to.to_string(),
+						"number": event.number.to_string(),
+					}),
+				))
+			}
+			ProposalsCommand::Votes {
+				proposal_id,
+				account,
+			} => {
+				let (yes, no) = XPredictLogic::proposal_count_vote(client, proposal_id).await?;
+				let minimum = XPredictLogic::proposal_minimum_vote(client).await?;
+				let mut value = json!({
+					"proposal_id": proposal_id,
+					"yes": yes.to_string(),
+					"no": no.to_string(),
+					"minimum_vote": minimum.to_string(),
+					"reached": yes.saturating_add(no) >= minimum,
+				});
+				if let Some(account) = account {
+					let vote = XPredictLogic::proposal_vote_stake(client, proposal_id, &account)
+						.await?
+						.map(|(number, opinion)| {
+							json!({
+								"number": number.to_string(),
+								"opinion": opinion,
+							})
+						});
+					value["account"] = account.to_string().into();
+					value["vote"] = vote.into();
+				}
+				Ok(value)
+			}
+			ProposalsCommand::Status { proposal_id } => {
+				let status = XPredictLogic::proposal_status(client, proposal_id).await?;
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"status": format!("{:?}", status),
+				}))
+			}
+			ProposalsCommand::Wait {
+				proposal_id,
+				status,
+				timeout_secs,
+			} => {
+				let timeout = Duration::from_secs(
+					timeout_secs.unwrap_or(ctx.config.timeouts.status_wait_secs),
+				);
+				XPredictLogic::wait_for_status(client, proposal_id, status, timeout).await?;
+				Ok(json!({
+					"proposal_id": proposal_id,
+					"status": format!("{:?}", status),
+				}))
+			}
+		}
+	}
+}
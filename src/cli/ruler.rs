@@ -0,0 +1,68 @@
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+use super::Context;
+use ws_client::{error::Result, logic::XPredictLogic, pallets::RulerModule, runtime::AccountId};
+
+pub fn parse_ruler_module(s: &str) -> std::result::Result<RulerModule, String> {
+	let module = match s.replace(&['-', '_'][..], "").to_lowercase().as_str() {
+		"platformdividend" => RulerModule::PlatformDividend,
+		"crosschainburn" => RulerModule::CrossChainBurn,
+		"notused" => RulerModule::NotUsed,
+		_ => return Err(format!("unknown ruler module {:?}", s)),
+	};
+	Ok(module)
+}
+
+#[derive(Debug, StructOpt)]
+pub enum RulerCommand {
+	/// Propose a new ruler address for a module
+	Transfer {
+		#[structopt(parse(try_from_str = parse_ruler_module))]
+		module: RulerModule,
+		address: AccountId,
+	},
+	/// Accept the pending ruler address of a module
+	Accept {
+		#[structopt(parse(try_from_str = parse_ruler_module))]
+		module: RulerModule,
+	},
+	/// Show the current and pending ruler of every module
+	Show,
+}
+
+impl RulerCommand {
+	pub async fn run(self, ctx: &Context) -> Result<Value> {
+		let client = &ctx.client;
+		match self {
+			RulerCommand::Transfer { module, address } => {
+				let pending =
+					XPredictLogic::ruler_transfer(client, &ctx.signer()?, module, &address).await?;
+				Ok(json!({
+					"module": format!("{:?}", module),
+					"pending": pending.to_string(),
+				}))
+			}
+			RulerCommand::Accept { module } => {
+				let ruler = XPredictLogic::ruler_accept(client, &ctx.signer()?, module).await?;
+				Ok(json!({
+					"module": format!("{:?}", module),
+					"ruler": ruler.to_string(),
+				}))
+			}
+			RulerCommand::Show => {
+				let mut rulers = Vec::new();
+				for module in RulerModule::ALL.iter().copied() {
+					let ruler = XPredictLogic::ruler_address(client, module).await?;
+					let pending = XPredictLogic::ruler_pending_address(client, module).await?;
+					rulers.push(json!({
+						"module": format!("{:?}", module),
+						"ruler": ruler.map(|account| account.to_string()),
+						"pending": pending.map(|account| account.to_string()),
+					}));
+				}
+				Ok(json!({ "rulers": rulers }))
+			}
+		}
+	}
+}
@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+
+use serde_json::json;
+use structopt::StructOpt;
+
+use super::{Context, OutputFormat};
+use ws_client::{
+	error::{Result, XPredictError},
+	scenario::{Runner, Scenario},
+};
+
+#[derive(Debug, StructOpt)]
+pub struct ScenarioCommand {
+	/// Scenario file, see `scenarios/demo.toml`
+	path: PathBuf,
+}
+
+impl ScenarioCommand {
+	/// Prints a report per step as it finishes, then a summary, and fails if
+	/// any step did.
+	pub async fn run(self, ctx: &Context, output: OutputFormat) -> Result<()> {
+		let scenario = Scenario::from_file(&self.path)?;
+		let mut runner = Runner::new(&ctx.client, &ctx.keystore, &ctx.config);
+		let report = runner
+			.run(&scenario, |step| output.print(&step.to_json()))
+			.await;
+		output.print(&json!({
+			"scenario": report.name,
+			"steps": report.steps.len(),
+			"failed": report.failed(),
+		}));
+		if !report.passed() {
+			return Err(XPredictError::Scenario(format!(
+				"{}: {} of {} steps failed",
+				report.name,
+				report.failed(),
+				report.steps.len()
+			)));
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,273 @@
+use std::marker::PhantomData;
+
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+use super::{output::submitted, Context};
+use ws_client::{
+	error::Result,
+	logic::{expect_event, sudo, XPredictLogic},
+	pallets::{tokens::*, Balance, CurrencyId},
+	runtime::AccountId,
+};
+
+#[derive(Debug, StructOpt)]
+pub enum TokensCommand {
+	/// Create a new asset (sudo)
+	NewAsset {
+		name: String,
+		symbol: String,
+		decimals: u8,
+	},
+	/// Mint tokens to an account (sudo)
+	Mint {
+		currency_id: CurrencyId,
+		to: AccountId,
+		number: Balance,
+	},
+	/// Burn tokens of the signer
+	Burn {
+		currency_id: CurrencyId,
+		number: Balance,
+	},
+	/// Burn tokens of `from` out of the signer's allowance
+	BurnFrom {
+		currency_id: CurrencyId,
+		from: AccountId,
+		number: Balance,
+	},
+	/// Transfer tokens to an account
+	Transfer {
+		currency_id: CurrencyId,
+		to: AccountId,
+		number: Balance,
+	},
+	/// Set the allowance of a spender
+	Approve {
+		currency_id: CurrencyId,
+		spender: AccountId,
+		number: Balance,
+	},
+	/// Increase the allowance of a spender
+	AddApprove {
+		currency_id: CurrencyId,
+		spender: AccountId,
+		number: Balance,
+	},
+	/// Transfer tokens of `from` out of the signer's allowance
+	TransferFrom {
+		currency_id: CurrencyId,
+		from: AccountId,
+		to: AccountId,
+		number: Balance,
+	},
+	/// Show the free and reserved balance of an account, currency 0 is the native token
+	Balance {
+		currency_id: CurrencyId,
+		account: AccountId,
+	},
+	/// Show every spender an owner approved, with the remaining allowance
+	Allowance {
+		currency_id: CurrencyId,
+		owner: AccountId,
+	},
+	/// Show name, symbol, decimals and total supply of a currency
+	Info { currency_id: CurrencyId },
+}
+
+impl TokensCommand {
+	pub async fn run(self, ctx: &Context) -> Result<Value> {
+		let client = &ctx.client;
+		match self {
+			TokensCommand::NewAsset {
+				name,
+				symbol,
+				decimals,
+			} => {
+				let call = client.encode(NewAssetCall {
+					name: name.into_bytes(),
+					symbol: symbol.into_bytes(),
+					decimals,
+					_runtime: PhantomData,
+				})?;
+				let result = sudo(client, &ctx.signer()?, &call).await?;
+				let event = expect_event::<NewAssetEvent<_>>(&result)?;
+				Ok(submitted(
+					&result,
+					json!({ "currency_id": event.currency_id }),
+				))
+			}
+			TokensCommand::Mint {
+				currency_id,
+				to,
+				number,
+			} => {
+				let call = client.encode(MintCall {
+					currency_id,
+					to: &to,
+					number,
+				})?;
+				let result = sudo(client, &ctx.signer()?, &call).await?;
+				let event = expect_event::<MintEvent<_>>(&result)?;
+				Ok(submitted(
+					&result,
+					json!({
+						"currency_id": event.currency_id,
+						"to": event.to.to_string(),
+						"number": event.number.to_string(),
+					}),
+				))
+			}
+			TokensCommand::Burn {
+				currency_id,
+				number,
+			} => {
+				let burned =
+					XPredictLogic::burn_token(client, &ctx.signer()?, currency_id, number).await?;
+				Ok(json!({
+					"currency_id": currency_id,
+					"number": burned.to_string(),
+				}))
+			}
+			TokensCommand::BurnFrom {
+				currency_id,
+				from,
+				number,
+			} => {
+				let burned = XPredictLogic::burn_token_from(
+					client,
+					&ctx.signer()?,
+					currency_id,
+					&from,
+					number,
+				)
+				.await?;
+				Ok(json!({
+					"currency_id": currency_id,
+					"from": from.to_string(),
+					"number": burned.to_string(),
+				}))
+			}
+			TokensCommand::Transfer {
+				currency_id,
+				to,
+				number,
+			} => {
+				let result = client
+					.transfer_and_watch(&ctx.signer()?, currency_id, &to, number)
+					.await?;
+				let event = expect_event::<TransferEvent<_>>(&result)?;
+				Ok(submitted(
+					&result,
+					json!({
+						"currency_id": event.currency_id,
+						"from": event.from.to_string(),
+						"to": event.to.to_string(),
+						"number": event.number.to_string(),
+					}),
+				))
+			}
+			TokensCommand::Approve {
+				currency_id,
+				spender,
+				number,
+			} => {
+				let approved = XPredictLogic::approve_token(
+					client,
+					&ctx.signer()?,
+					currency_id,
+					&spender,
+					number,
+				)
+				.await?;
+				Ok(approval(currency_id, &spender, approved))
+			}
+			TokensCommand::AddApprove {
+				currency_id,
+				spender,
+				number,
+			} => {
+				let approved = XPredictLogic::add_approve_token(
+					client,
+					&ctx.signer()?,
+					currency_id,
+					&spender,
+					number,
+				)
+				.await?;
+				Ok(approval(currency_id, &spender, approved))
+			}
+			TokensCommand::TransferFrom {
+				currency_id,
+				from,
+				to,
+				number,
+			} => {
+				let transferred = XPredictLogic::transfer_token_from(
+					client,
+					&ctx.signer()?,
+					currency_id,
+					&from,
+					&to,
+					number,
+				)
+				.await?;
+				Ok(json!({
+					"currency_id": currency_id,
+					"from": from.to_string(),
+					"to": to.to_string(),
+					"number": transferred.to_string(),
+				}))
+			}
+			TokensCommand::Balance {
+				currency_id,
+				account,
+			} => {
+				let balance = XPredictLogic::balance_of(client, currency_id, &account).await?;
+				let reserved = XPredictLogic::reserve_of(client, currency_id, &account).await?;
+				Ok(json!({
+					"currency_id": currency_id,
+					"account": account.to_string(),
+					"balance": balance.to_string(),
+					"reserved": reserved.to_string(),
+				}))
+			}
+			TokensCommand::Allowance { currency_id, owner } => {
+				let allowances = XPredictLogic::allowance(client, currency_id, &owner)
+					.await?
+					.into_iter()
+					.map(|(spender, number)| {
+						json!({
+							"spender": spender.to_string(),
+							"number": number.to_string(),
+						})
+					})
+					.collect::<Vec<_>>();
+				Ok(json!({
+					"currency_id": currency_id,
+					"owner": owner.to_string(),
+					"allowances": allowances,
+				}))
+			}
+			TokensCommand::Info { currency_id } => {
+				let token = XPredictLogic::currencies(client, currency_id).await?;
+				let total_supply = XPredictLogic::total_supply(client, currency_id).await?;
+				Ok(json!({
+					"currency_id": currency_id,
+					"name": String::from_utf8_lossy(&token.name),
+					"symbol": String::from_utf8_lossy(&token.symbol),
+					"decimals": token.decimals,
+					"total_supply": total_supply.to_string(),
+				}))
+			}
+		}
+	}
+}
+
+fn approval(currency_id: CurrencyId, spender: &AccountId, number: Balance) -> Value {
+	json!({
+		"currency_id": currency_id,
+		"spender": spender.to_string(),
+		"number": number.to_string(),
+	})
+}
@@ -0,0 +1,446 @@
+use std::{
+	env, fs,
+	path::{Path, PathBuf},
+	str::FromStr,
+};
+
+use serde::Deserialize;
+
+use crate::{
+	error::{Result, XPredictError},
+	keystore::{KeyScheme, KeySource},
+	pallets::{Balance, CategoryId, CurrencyId},
+	runtime::BlockNumber,
+};
+
+pub const DEFAULT_CONFIG_FILE: &str = "xpredict.toml";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct XPredictConfig {
+	pub endpoint: String,
+	pub timeouts: TimeoutConfig,
+	pub fees: FeeConfig,
+	pub proposal: ProposalConfig,
+	pub keys: KeysConfig,
+	pub indexer: IndexerConfig,
+	pub keeper: KeeperConfig,
+	pub maker: MakerConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TimeoutConfig {
+	pub status_wait_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FeeConfig {
+	pub earn_fee: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProposalConfig {
+	pub close_after_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IndexerConfig {
+	/// SQLite database the indexer writes to and resumes from
+	pub database: PathBuf,
+	/// First block indexed into an empty database
+	pub start_block: BlockNumber,
+}
+
+/// Which status transitions the keeper drives when the chain has not made
+/// them `grace_secs` after they were due.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct KeeperConfig {
+	/// JSON lines file the keeper appends its actions to
+	pub journal: PathBuf,
+	pub grace_secs: u64,
+	/// Original predictions to `FormalPrediction` once `MinimumVote` is reached
+	pub promote_voted: bool,
+	/// Formal predictions to `WaitingForResults` after their close time
+	pub close: bool,
+	/// `WaitingForResults` to `ResultAnnouncement` once the publicity interval
+	/// after the close time has passed
+	pub announce: bool,
+	/// Announced proposals to the result with the most uploaded stake once
+	/// the report interval after the announcement has passed
+	pub resolve: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MakerConfig {
+	/// JSON file the positions are kept in across runs
+	pub state: PathBuf,
+	/// Proposal currencies to provide liquidity for, any when empty
+	pub currencies: Vec<CurrencyId>,
+	/// Proposal categories to provide liquidity for, any when empty
+	pub categories: Vec<CategoryId>,
+	pub min_volume: Balance,
+	/// Proposal currency added to each matching proposal
+	pub liquidity: Balance,
+	/// Yes probability the maker trades the pool toward, no trading when unset
+	pub target_probability: Option<f64>,
+	/// Distance from the target tolerated before trading
+	pub tolerance: f64,
+	/// Largest buy or sell, in proposal currency or tokens
+	pub max_trade: Balance,
+	/// Liquidity is pulled this many seconds before the close time
+	pub exit_before_close_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct KeysConfig {
+	pub admin: Vec<KeySource>,
+	pub normal: Vec<KeySource>,
+	/// Keys for the ed25519 and ecdsa schemes; the lists above are sr25519
+	pub ed25519: KeyRoles,
+	pub ecdsa: KeyRoles,
+	/// Directory of an `EncryptedKeystore` whose keys are appended to the ones above
+	pub keystore: Option<PathBuf>,
+	pub keystore_password_env: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct KeyRoles {
+	pub admin: Vec<KeySource>,
+	pub normal: Vec<KeySource>,
+}
+
+/// Settings given on the command line, the last layer of the configuration.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+	pub endpoint: Option<String>,
+	pub status_wait_secs: Option<u64>,
+	pub earn_fee: Option<u32>,
+	pub close_after_secs: Option<u64>,
+}
+
+impl Default for XPredictConfig {
+	fn default() -> Self {
+		XPredictConfig {
+			endpoint: "ws://127.0.0.1:9944".into(),
+			timeouts: TimeoutConfig::default(),
+			fees: FeeConfig::default(),
+			proposal: ProposalConfig::default(),
+			keys: KeysConfig::default(),
+			indexer: IndexerConfig::default(),
+			keeper: KeeperConfig::default(),
+			maker: MakerConfig::default(),
+		}
+	}
+}
+
+impl Default for TimeoutConfig {
+	fn default() -> Self {
+		TimeoutConfig {
+			status_wait_secs: 30 * 60,
+		}
+	}
+}
+
+impl Default for FeeConfig {
+	fn default() -> Self {
+		FeeConfig { earn_fee: 2000 }
+	}
+}
+
+impl Default for ProposalConfig {
+	fn default() -> Self {
+		ProposalConfig {
+			close_after_secs: 11 * 60,
+		}
+	}
+}
+
+impl Default for IndexerConfig {
+	fn default() -> Self {
+		IndexerConfig {
+			database: "xpredict-index.sqlite".into(),
+			start_block: 0,
+		}
+	}
+}
+
+impl Default for KeeperConfig {
+	fn default() -> Self {
+		KeeperConfig {
+			journal: "xpredict-keeper.jsonl".into(),
+			grace_secs: 60,
+			promote_voted: true,
+			close: true,
+			announce: true,
+			resolve: true,
+		}
+	}
+}
+
+impl Default for MakerConfig {
+	fn default() -> Self {
+		MakerConfig {
+			state: "xpredict-maker.json".into(),
+			currencies: Vec::new(),
+			categories: Vec::new(),
+			min_volume: 0,
+			liquidity: 0,
+			target_probability: None,
+			tolerance: 0.02,
+			max_trade: 0,
+			exit_before_close_secs: 120,
+		}
+	}
+}
+
+impl Default for KeysConfig {
+	fn default() -> Self {
+		let dev = |uri: &str| KeySource::Uri(uri.into());
+		KeysConfig {
+			admin: vec![dev("//Alice")],
+			normal: vec![
+				dev("//Bob"),
+				dev("//Charlie"),
+				dev("//Dave"),
+				dev("//Eve"),
+				dev("//Ferdie"),
+				dev("//One"),
+				dev("//Two"),
+			],
+			ed25519: KeyRoles::default(),
+			ecdsa: KeyRoles::default(),
+			keystore: None,
+			keystore_password_env: "XPREDICT_KEYSTORE_PASSWORD".into(),
+		}
+	}
+}
+
+impl KeysConfig {
+	pub fn sources(&self, scheme: KeyScheme) -> (&[KeySource], &[KeySource]) {
+		match scheme {
+			KeyScheme::Sr25519 => (&self.admin, &self.normal),
+			KeyScheme::Ed25519 => (&self.ed25519.admin, &self.ed25519.normal),
+			KeyScheme::Ecdsa => (&self.ecdsa.admin, &self.ecdsa.normal),
+		}
+	}
+}
+
+impl XPredictConfig {
+	/// Builds the configuration from the defaults, the TOML file at `path`
+	/// (or `xpredict.toml` in the working directory when it exists), the
+	/// `XPREDICT_*` environment variables and the command line `overrides`,
+	/// later layers winning.
+	pub fn load(path: Option<&Path>, overrides: &ConfigOverrides) -> Result<Self> {
+		let mut config = match path {
+			Some(path) => Self::from_file(path)?,
+			None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+				Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+			}
+			None => Self::default(),
+		};
+		config.apply_env()?;
+		config.apply_overrides(overrides);
+		Ok(config)
+	}
+
+	pub fn from_file(path: &Path) -> Result<Self> {
+		let content = fs::read_to_string(path)
+			.map_err(|e| XPredictError::Config(format!("{}: {}", path.display(), e)))?;
+		toml::from_str(&content)
+			.map_err(|e| XPredictError::Config(format!("{}: {}", path.display(), e)))
+	}
+
+	fn apply_env(&mut self) -> Result<()> {
+		env_override("XPREDICT_ENDPOINT", &mut self.endpoint)?;
+		env_override(
+			"XPREDICT_STATUS_WAIT_SECS",
+			&mut self.timeouts.status_wait_secs,
+		)?;
+		env_override("XPREDICT_EARN_FEE", &mut self.fees.earn_fee)?;
+		env_override(
+			"XPREDICT_CLOSE_AFTER_SECS",
+			&mut self.proposal.close_after_secs,
+		)?;
+		env_list_override("XPREDICT_ADMIN_KEYS", &mut self.keys.admin);
+		env_list_override("XPREDICT_NORMAL_KEYS", &mut self.keys.normal);
+		if let Ok(path) = env::var("XPREDICT_KEYSTORE") {
+			self.keys.keystore = Some(path.into());
+		}
+		if let Ok(path) = env::var("XPREDICT_INDEX_DATABASE") {
+			self.indexer.database = path.into();
+		}
+		env_override("XPREDICT_INDEX_START_BLOCK", &mut self.indexer.start_block)?;
+		if let Ok(path) = env::var("XPREDICT_KEEPER_JOURNAL") {
+			self.keeper.journal = path.into();
+		}
+		env_override("XPREDICT_KEEPER_GRACE_SECS", &mut self.keeper.grace_secs)?;
+		if let Ok(path) = env::var("XPREDICT_MAKER_STATE") {
+			self.maker.state = path.into();
+		}
+		Ok(())
+	}
+
+	fn apply_overrides(&mut self, overrides: &ConfigOverrides) {
+		if let Some(endpoint) = &overrides.endpoint {
+			self.endpoint = endpoint.clone();
+		}
+		if let Some(secs) = overrides.status_wait_secs {
+			self.timeouts.status_wait_secs = secs;
+		}
+		if let Some(fee) = overrides.earn_fee {
+			self.fees.earn_fee = fee;
+		}
+		if let Some(secs) = overrides.close_after_secs {
+			self.proposal.close_after_secs = secs;
+		}
+	}
+}
+
+fn env_override<T: FromStr>(name: &str, target: &mut T) -> Result<()> {
+	if let Ok(value) = env::var(name) {
+		*target = value
+			.parse()
+			.map_err(|_| XPredictError::Config(format!("{}: invalid value {:?}", name, value)))?;
+	}
+	Ok(())
+}
+
+fn env_list_override(name: &str, target: &mut Vec<KeySource>) {
+	if let Ok(value) = env::var(name) {
+		*target = value
+			.split(',')
+			.map(str::trim)
+			.filter(|item| !item.is_empty())
+			.map(|item| KeySource::Uri(item.into()))
+			.collect();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A TOML file removed when the test is done.
+	struct TempFile(PathBuf);
+
+	impl TempFile {
+		fn new(name: &str, content: &str) -> Self {
+			let path = env::temp_dir().join(format!(
+				"xpredict-config-{}-{}.toml",
+				name,
+				std::process::id()
+			));
+			fs::write(&path, content).unwrap();
+			TempFile(path)
+		}
+	}
+
+	impl Drop for TempFile {
+		fn drop(&mut self) {
+			let _ = fs::remove_file(&self.0);
+		}
+	}
+
+	// Only this test sets the variables it reads, the others run in parallel.
+	#[test]
+	fn later_layers_win() {
+		let file = TempFile::new(
+			"layers",
+			r#"
+				endpoint = "ws://toml:9944"
+				[fees]
+				earn_fee = 1000
+				[proposal]
+				close_after_secs = 100
+				[timeouts]
+				status_wait_secs = 10
+			"#,
+		);
+		env::set_var("XPREDICT_EARN_FEE", "1500");
+		env::set_var("XPREDICT_CLOSE_AFTER_SECS", "200");
+		let overrides = ConfigOverrides {
+			close_after_secs: Some(300),
+			..Default::default()
+		};
+		let config = XPredictConfig::load(Some(&file.0), &overrides);
+		env::remove_var("XPREDICT_EARN_FEE");
+		env::remove_var("XPREDICT_CLOSE_AFTER_SECS");
+		let config = config.unwrap();
+
+		// defaults < TOML
+		assert_eq!(config.endpoint, "ws://toml:9944");
+		assert_eq!(config.timeouts.status_wait_secs, 10);
+		// TOML < env
+		assert_eq!(config.fees.earn_fee, 1500);
+		// env < command line
+		assert_eq!(config.proposal.close_after_secs, 300);
+		// untouched by every layer
+		assert_eq!(config.keeper.grace_secs, 60);
+		assert_eq!(config.keys.admin.len(), 1);
+	}
+
+	#[test]
+	fn missing_sections_keep_the_defaults() {
+		let config: XPredictConfig = toml::from_str("[maker]\nliquidity = 5").unwrap();
+		assert_eq!(config.maker.liquidity, 5);
+		assert_eq!(config.maker.tolerance, 0.02);
+		assert_eq!(config.endpoint, "ws://127.0.0.1:9944");
+		assert_eq!(config.fees.earn_fee, 2000);
+		assert_eq!(config.proposal.close_after_secs, 11 * 60);
+	}
+
+	#[test]
+	fn command_line_overrides_every_field() {
+		let mut config = XPredictConfig::default();
+		config.apply_overrides(&ConfigOverrides {
+			endpoint: Some("ws://cli:9944".into()),
+			status_wait_secs: Some(1),
+			earn_fee: Some(2),
+			close_after_secs: Some(3),
+		});
+		assert_eq!(config.endpoint, "ws://cli:9944");
+		assert_eq!(config.timeouts.status_wait_secs, 1);
+		assert_eq!(config.fees.earn_fee, 2);
+		assert_eq!(config.proposal.close_after_secs, 3);
+	}
+
+	#[test]
+	fn rejects_invalid_values() {
+		let file = TempFile::new("invalid", "[fees]\nearn_fee = \"high\"");
+		assert!(matches!(
+			XPredictConfig::from_file(&file.0),
+			Err(XPredictError::Config(_))
+		));
+
+		env::set_var("XPREDICT_TEST_INVALID_NUMBER", "many");
+		let mut target = 0u64;
+		let result = env_override("XPREDICT_TEST_INVALID_NUMBER", &mut target);
+		env::remove_var("XPREDICT_TEST_INVALID_NUMBER");
+		assert!(matches!(result, Err(XPredictError::Config(e)) if e.contains("many")));
+		assert_eq!(target, 0);
+	}
+
+	#[test]
+	fn splits_key_lists() {
+		env::set_var("XPREDICT_TEST_KEYS", "//Alice, ,//Bob");
+		let mut keys = Vec::new();
+		env_list_override("XPREDICT_TEST_KEYS", &mut keys);
+		env::remove_var("XPREDICT_TEST_KEYS");
+		let uris: Vec<_> = keys
+			.iter()
+			.map(|key| match key {
+				KeySource::Uri(uri) => uri.as_str(),
+				_ => panic!("not an uri"),
+			})
+			.collect();
+		assert_eq!(uris, ["//Alice", "//Bob"]);
+	}
+}
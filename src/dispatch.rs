@@ -0,0 +1,49 @@
+use std::fmt;
+
+use sp_runtime::DispatchError;
+use subxt::{Metadata, RuntimeError};
+
+/// A failed dispatch with module and error indices resolved to their names,
+/// e.g. `Couple::InsufficientLiquidity`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DispatchFailure {
+	Module { pallet: String, error: String },
+	BadOrigin,
+	CannotLookup,
+	Other(String),
+}
+
+impl fmt::Display for DispatchFailure {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			DispatchFailure::Module { pallet, error } => write!(f, "{}::{}", pallet, error),
+			DispatchFailure::BadOrigin => write!(f, "BadOrigin"),
+			DispatchFailure::CannotLookup => write!(f, "CannotLookup"),
+			DispatchFailure::Other(message) => write!(f, "{}", message),
+		}
+	}
+}
+
+impl From<RuntimeError> for DispatchFailure {
+	fn from(error: RuntimeError) -> Self {
+		match error {
+			RuntimeError::Module(e) => DispatchFailure::Module {
+				pallet: e.module,
+				error: e.error,
+			},
+			RuntimeError::BadOrigin => DispatchFailure::BadOrigin,
+			RuntimeError::CannotLookup => DispatchFailure::CannotLookup,
+			e => DispatchFailure::Other(e.to_string()),
+		}
+	}
+}
+
+/// Resolves a raw `DispatchError`, such as the one carried by `Sudo::Sudid`,
+/// against the runtime metadata. An error the metadata does not know is kept
+/// raw, the dispatch failed either way.
+pub fn decode_dispatch_error(metadata: &Metadata, error: DispatchError) -> DispatchFailure {
+	match RuntimeError::from_dispatch(metadata, error.clone()) {
+		Ok(error) => error.into(),
+		Err(_) => DispatchFailure::Other(format!("{:?}", error)),
+	}
+}
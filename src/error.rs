@@ -0,0 +1,127 @@
+use std::{fmt::Debug, time::Duration};
+
+use subxt::{Event, Store};
+
+use crate::{
+	dispatch::DispatchFailure,
+	pallets::{Balance, ProposalId, ProposalStatus},
+	runtime::{BlockNumber, Hash, XPredictRuntime},
+};
+
+pub type Result<T> = std::result::Result<T, XPredictError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum XPredictError {
+	#[error("rpc error: {0}")]
+	Rpc(subxt::Error),
+	#[error("decode error: {0}")]
+	Decode(#[from] codec::Error),
+	#[error("storage entry {module}::{field} not found for key {key}")]
+	StorageNotFound {
+		module: &'static str,
+		field: &'static str,
+		key: String,
+	},
+	#[error("extrinsic failed: {0}")]
+	Extrinsic(DispatchFailure),
+	/// Calls before `index` went through, the rest were not dispatched
+	#[error("batch interrupted at call {index}: {error}")]
+	BatchInterrupted { index: u32, error: DispatchFailure },
+	/// The key of a map entry whose hasher cannot be reversed
+	#[error("cannot decode a {module}::{field} key from {key}")]
+	StorageKey {
+		module: &'static str,
+		field: &'static str,
+		key: String,
+	},
+	#[error("event {module}::{event} not found in block {block:?}")]
+	EventNotFound {
+		module: &'static str,
+		event: &'static str,
+		block: Hash,
+	},
+	#[error("cannot decode event {module}::{event} in block {block_number}: {error}")]
+	UndecodableEvent {
+		block_number: BlockNumber,
+		module: String,
+		event: String,
+		error: codec::Error,
+	},
+	#[error("block {0} not found")]
+	BlockNotFound(BlockNumber),
+	/// `None` stands for the best block
+	#[error("header {0:?} not found")]
+	HeaderNotFound(Option<Hash>),
+	#[error("proposal {proposal_id} is {found:?}, expected {expected:?}")]
+	UnexpectedStatus {
+		proposal_id: ProposalId,
+		expected: ProposalStatus,
+		found: ProposalStatus,
+	},
+	#[error("subscription closed by the node")]
+	SubscriptionClosed,
+	#[error("proposal {proposal_id} did not reach {target:?} within {timeout:?}")]
+	StatusTimeout {
+		proposal_id: ProposalId,
+		target: ProposalStatus,
+		timeout: Duration,
+	},
+	#[error("invalid timestamp: {0}")]
+	Timestamp(String),
+	#[error("quote error: {0}")]
+	Quote(String),
+	#[error("proposal {proposal_id} trade returns {output}, below the minimum {minimum}")]
+	BelowMinimum {
+		proposal_id: ProposalId,
+		output: Balance,
+		minimum: Balance,
+	},
+	#[error("proposal {proposal_id} price would move to {price}, past the bound {bound}")]
+	PriceBound {
+		proposal_id: ProposalId,
+		price: f64,
+		bound: f64,
+	},
+	#[error("keeper error: {0}")]
+	Keeper(String),
+	#[error("market maker error: {0}")]
+	Maker(String),
+	#[error("oracle error: {0}")]
+	Oracle(String),
+	#[error("scenario error: {0}")]
+	Scenario(String),
+	#[error("config error: {0}")]
+	Config(String),
+	#[error("keystore error: {0}")]
+	Keystore(String),
+	#[error("database error: {0}")]
+	Database(#[from] rusqlite::Error),
+}
+
+impl XPredictError {
+	pub fn not_found<S: Store<XPredictRuntime>>(key: impl Debug) -> Self {
+		XPredictError::StorageNotFound {
+			module: S::MODULE,
+			field: S::FIELD,
+			key: format!("{:?}", key),
+		}
+	}
+
+	pub fn event_not_found<E: Event<XPredictRuntime>>(block: Hash) -> Self {
+		XPredictError::EventNotFound {
+			module: E::MODULE,
+			event: E::EVENT,
+			block,
+		}
+	}
+}
+
+impl From<subxt::Error> for XPredictError {
+	fn from(error: subxt::Error) -> Self {
+		match error {
+			subxt::Error::Codec(e) => XPredictError::Decode(e),
+			subxt::Error::Runtime(e) => XPredictError::Extrinsic(e.into()),
+			e => XPredictError::Rpc(e),
+		}
+	}
+}
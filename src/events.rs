@@ -0,0 +1,384 @@
+use std::collections::VecDeque;
+
+use codec::{Decode, Input};
+use futures::{future, stream, Stream, StreamExt};
+use serde_json::{json, Map, Value};
+use sp_core::{hashing::twox_128, storage::StorageKey};
+use sp_runtime::traits::Header;
+use subxt::{events::Raw, system::Phase, Client, Event, RawEvent};
+
+use crate::{
+	error::{Result, XPredictError},
+	pallets::{
+		autonomy::*,
+		couple::{self, *},
+		proposals::{self, *},
+		ruler::*,
+		tokens::*,
+		Balance, Moment, ProposalId, ProposalStatus, RulerModule,
+	},
+	runtime::{AccountId, BlockNumber, Hash, XPredictRuntime},
+};
+
+/// An XPredict pallet event together with where it was emitted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EventRecord {
+	pub block_number: BlockNumber,
+	pub block_hash: Hash,
+	/// Index of the event in the block's `System.Events`, counting the
+	/// events of every pallet
+	pub event_index: u32,
+	/// `None` for events emitted outside of an extrinsic, e.g. `on_finalize`
+	pub extrinsic_index: Option<u32>,
+	pub event: XPredictEvent,
+}
+
+impl EventRecord {
+	pub fn to_json(&self) -> Value {
+		json!({
+			"block_number": self.block_number,
+			"block_hash": format!("{:?}", self.block_hash),
+			"event_index": self.event_index,
+			"extrinsic_index": self.extrinsic_index,
+			"module": self.event.module(),
+			"event": self.event.name(),
+			"data": self.event.data(),
+		})
+	}
+}
+
+trait EventField {
+	fn to_json(&self) -> Value;
+}
+
+impl EventField for AccountId {
+	fn to_json(&self) -> Value {
+		self.to_string().into()
+	}
+}
+
+impl EventField for u32 {
+	fn to_json(&self) -> Value {
+		(*self).into()
+	}
+}
+
+impl EventField for Moment {
+	fn to_json(&self) -> Value {
+		(*self).into()
+	}
+}
+
+impl EventField for Balance {
+	fn to_json(&self) -> Value {
+		self.to_string().into()
+	}
+}
+
+impl EventField for bool {
+	fn to_json(&self) -> Value {
+		(*self).into()
+	}
+}
+
+impl EventField for ProposalStatus {
+	fn to_json(&self) -> Value {
+		format!("{:?}", self).into()
+	}
+}
+
+impl EventField for RulerModule {
+	fn to_json(&self) -> Value {
+		format!("{:?}", self).into()
+	}
+}
+
+fn is_event<E: Event<XPredictRuntime>>(raw: &RawEvent) -> bool {
+	raw.module == E::MODULE && raw.variant == E::EVENT
+}
+
+fn module_of<E: Event<XPredictRuntime>>(_: &E) -> &'static str {
+	E::MODULE
+}
+
+fn name_of<E: Event<XPredictRuntime>>(_: &E) -> &'static str {
+	E::EVENT
+}
+
+macro_rules! xpredict_events {
+	($($variant:ident($($event:ident)::+) { $($field:ident),* }),* $(,)?) => {
+		/// Every event of the tokens, couple, proposals, autonomy and ruler
+		/// pallets.
+		#[derive(Clone, Debug, Eq, PartialEq)]
+		pub enum XPredictEvent {
+			$($variant($($event)::+<XPredictRuntime>),)*
+		}
+
+		impl XPredictEvent {
+			/// Decodes `raw` if it belongs to one of the XPredict pallets.
+			pub fn decode(raw: &RawEvent) -> Result<Option<Self>> {
+				$(
+					if is_event::<$($event)::+<XPredictRuntime>>(raw) {
+						let event = Decode::decode(&mut &raw.data[..])?;
+						return Ok(Some(XPredictEvent::$variant(event)));
+					}
+				)*
+				Ok(None)
+			}
+
+			pub fn module(&self) -> &'static str {
+				match self {
+					$(XPredictEvent::$variant(event) => module_of(event),)*
+				}
+			}
+
+			pub fn name(&self) -> &'static str {
+				match self {
+					$(XPredictEvent::$variant(event) => name_of(event),)*
+				}
+			}
+
+			/// The event fields as JSON, balances as strings.
+			pub fn data(&self) -> Value {
+				match self {
+					$(XPredictEvent::$variant(_event) => {
+						#[allow(unused_mut)]
+						let mut data = Map::new();
+						$(data.insert(stringify!($field).into(), _event.$field.to_json());)*
+						Value::Object(data)
+					})*
+				}
+			}
+		}
+	};
+}
+
+xpredict_events! {
+	NewAsset(NewAssetEvent) { currency_id },
+	Mint(MintEvent) { currency_id, to, number },
+	Burn(BurnEvent) { currency_id, to, number },
+	BurnFrom(BurnFromEvent) { currency_id, from, to, number },
+	Transfer(TransferEvent) { currency_id, from, to, number },
+	TransferFrom(TransferFromEvent) { currency_id, who, from, to, number },
+	Approval(ApprovalEvent) { currency_id, from, to, number },
+	NewProposal(NewProposalEvent) { who, proposal_id, currency_id },
+	AddLiquidity(AddLiquidityEvent) { from, proposal_id, currency_id, number },
+	RemoveLiquidity(RemoveLiquiditEvent) { from, proposal_id, currency_id, number },
+	Buy(BuyEvent) { from, proposal_id, currency_id, number },
+	Sell(SellEvent) { from, proposal_id, currency_id, number },
+	Retrieval(RetrievalEvent) { from, proposal_id, currency_id, number },
+	SetResult(SetResultEvent) { proposal_id, currency_id },
+	CoupleWithdrawalReward(couple::WithdrawalRewardEvent) { from, proposal_id, number },
+	ProposalStatusChanged(ProposalStatusChangedEvent) { proposal_id, state },
+	StakeTo(StakeToEvent) { who, proposal_id, number },
+	UnStakeFrom(UnStakeFromEvent) { who, proposal_id, number },
+	DepositReward(DepositRewardEvent) { from, to, number },
+	ReclaimReward(ReclaimRewardEvent) { from, to, number },
+	ProposalsWithdrawalReward(proposals::WithdrawalRewardEvent) { who, proposal_id, number },
+	Stake(StakeEvent) { from, number },
+	UnStake(UnStakeEvent) { from, number },
+	Slash(SlashEvent) { who, number },
+	Tagging(TaggingEvent) { who },
+	UnTagging(UnTaggingEvent) { who },
+	UploadResult(UploadResultEvent) { who, proposal_id, currency_id },
+	SetMinimalNumber(SetMinimalNumberEvent) { number },
+	SetPublicityInterval(SetPublicityIntervalEvent) { time },
+	Report(ReportEvent) { from, proposal_id, to, number },
+	SecondedReport(SecondedReportEvent) { from, proposal_id, to, approve },
+	TakeOut(TakeOutEvent) { from, proposal_id, to, number },
+	PendingRulerAddress(PendingRulerAddressEvent) { ruler_module, from, to },
+	AcceptRulerAddress(AcceptRulerAddressEvent) { ruler_module, account },
+}
+
+/// The remaining bytes of a storage value, for values decoded elsewhere.
+struct RawStorage(Vec<u8>);
+
+impl Decode for RawStorage {
+	fn decode<I: Input>(input: &mut I) -> std::result::Result<Self, codec::Error> {
+		let len = input
+			.remaining_len()?
+			.ok_or_else(|| codec::Error::from("unknown storage value length"))?;
+		let mut data = vec![0; len];
+		input.read(&mut data)?;
+		Ok(RawStorage(data))
+	}
+}
+
+fn events_key() -> StorageKey {
+	let mut key = twox_128(b"System").to_vec();
+	key.extend_from_slice(&twox_128(b"Events"));
+	StorageKey(key)
+}
+
+/// Decodes the XPredict events of block `block_hash`, in emission order.
+/// Fails if one of them does not decode, see `decode_block_events`.
+pub async fn block_events(
+	client: &Client<XPredictRuntime>,
+	block_number: BlockNumber,
+	block_hash: Hash,
+) -> Result<Vec<EventRecord>> {
+	decode_block_events(client, block_number, block_hash)
+		.await?
+		.into_iter()
+		.collect()
+}
+
+/// Like `block_events`, with an `UndecodableEvent` error in place of every
+/// XPredict event that does not decode instead of failing the block.
+pub async fn decode_block_events(
+	client: &Client<XPredictRuntime>,
+	block_number: BlockNumber,
+	block_hash: Hash,
+) -> Result<Vec<Result<EventRecord>>> {
+	let data = match client
+		.fetch_unhashed::<RawStorage>(events_key(), Some(block_hash))
+		.await?
+	{
+		Some(RawStorage(data)) => data,
+		None => return Ok(Vec::new()),
+	};
+	let mut records = Vec::new();
+	let events = client.events_decoder().decode_events(&mut &data[..])?;
+	for (event_index, (phase, raw)) in events.into_iter().enumerate() {
+		let raw = match raw {
+			Raw::Event(raw) => raw,
+			Raw::Error(_) => continue,
+		};
+		let event = match XPredictEvent::decode(&raw) {
+			Ok(Some(event)) => event,
+			Ok(None) => continue,
+			Err(XPredictError::Decode(error)) => {
+				records.push(Err(XPredictError::UndecodableEvent {
+					block_number,
+					module: raw.module.clone(),
+					event: raw.variant.clone(),
+					error,
+				}));
+				continue;
+			}
+			Err(e) => return Err(e),
+		};
+		let extrinsic_index = match phase {
+			Phase::ApplyExtrinsic(index) => Some(index),
+			_ => None,
+		};
+		records.push(Ok(EventRecord {
+			block_number,
+			block_hash,
+			event_index: event_index as u32,
+			extrinsic_index,
+			event,
+		}));
+	}
+	Ok(records)
+}
+
+/// Decodes the XPredict events of block number `block_number` of the best
+/// chain.
+pub async fn block_events_at(
+	client: &Client<XPredictRuntime>,
+	block_number: BlockNumber,
+) -> Result<Vec<EventRecord>> {
+	let block_hash = block_hash(client, block_number).await?;
+	block_events(client, block_number, block_hash).await
+}
+
+async fn block_hash(client: &Client<XPredictRuntime>, block_number: BlockNumber) -> Result<Hash> {
+	client
+		.block_hash(Some(block_number.into()))
+		.await?
+		.ok_or(XPredictError::BlockNotFound(block_number))
+}
+
+/// Follows new (or only finalized) blocks and yields their XPredict events.
+/// Blocks the subscription skipped are fetched by number from the last one
+/// seen. Events that do not decode, and blocks that cannot be read, are
+/// yielded as errors and the stream goes on.
+pub async fn subscribe_events(
+	client: &Client<XPredictRuntime>,
+	finalized: bool,
+) -> Result<impl Stream<Item = Result<EventRecord>> + '_> {
+	let headers = if finalized {
+		client.subscribe_finalized_blocks().await?
+	} else {
+		client.subscribe_blocks().await?
+	};
+	Ok(stream::unfold(
+		(headers, VecDeque::new(), None::<BlockNumber>),
+		move |(mut headers, mut pending, mut last)| async move {
+			loop {
+				if let Some(record) = pending.pop_front() {
+					return Some((record, (headers, pending, last)));
+				}
+				let header = match headers.next().await {
+					Ok(Some(header)) => header,
+					Ok(None) => return None,
+					Err(e) => {
+						let error = XPredictError::from(subxt::Error::from(e));
+						return Some((Err(error), (headers, pending, last)));
+					}
+				};
+				let from = match last {
+					Some(last) if header.number > last + 1 => last + 1,
+					_ => header.number,
+				};
+				for number in from..header.number {
+					let records = match block_hash(client, number).await {
+						Ok(hash) => decode_block_events(client, number, hash).await,
+						Err(e) => Err(e),
+					};
+					push_records(&mut pending, records);
+				}
+				let records = decode_block_events(client, header.number, header.hash()).await;
+				push_records(&mut pending, records);
+				last = Some(header.number);
+			}
+		},
+	))
+}
+
+fn push_records(
+	pending: &mut VecDeque<Result<EventRecord>>,
+	records: Result<Vec<Result<EventRecord>>>,
+) {
+	match records {
+		Ok(records) => pending.extend(records),
+		Err(e) => pending.push_back(Err(e)),
+	}
+}
+
+/// A `ProposalStatusChangedEvent` on the best chain.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StatusTransition {
+	pub proposal_id: ProposalId,
+	pub status: ProposalStatus,
+	pub block_number: BlockNumber,
+	pub block_hash: Hash,
+}
+
+/// Yields the status changes of `proposal_id`, or of every proposal when
+/// `None`, as they are emitted.
+pub async fn status_transitions(
+	client: &Client<XPredictRuntime>,
+	proposal_id: Option<ProposalId>,
+) -> Result<impl Stream<Item = Result<StatusTransition>> + '_> {
+	let events = subscribe_events(client, false).await?;
+	Ok(events.filter_map(move |record| {
+		let transition = match record {
+			Ok(EventRecord {
+				block_number,
+				block_hash,
+				event: XPredictEvent::ProposalStatusChanged(event),
+				..
+			}) if proposal_id.map_or(true, |id| id == event.proposal_id) => Some(Ok(StatusTransition {
+				proposal_id: event.proposal_id,
+				status: event.state,
+				block_number,
+				block_hash,
+			})),
+			Ok(_) => None,
+			Err(e) => Some(Err(e)),
+		};
+		future::ready(transition)
+	}))
+}
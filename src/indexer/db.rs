@@ -0,0 +1,638 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection, Transaction};
+
+use crate::{
+	error::Result,
+	events::{EventRecord, XPredictEvent},
+	pallets::{Balance, CurrencyId, ProposalId},
+	runtime::{AccountId, BlockNumber, Hash},
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS blocks (
+	number INTEGER PRIMARY KEY,
+	hash TEXT NOT NULL,
+	finalized INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS proposals (
+	proposal_id INTEGER PRIMARY KEY,
+	creator TEXT NOT NULL,
+	currency_id INTEGER NOT NULL,
+	block_number INTEGER NOT NULL,
+	extrinsic_index INTEGER
+);
+CREATE TABLE IF NOT EXISTS proposal_status_changes (
+	block_number INTEGER NOT NULL,
+	event_index INTEGER NOT NULL,
+	extrinsic_index INTEGER,
+	proposal_id INTEGER NOT NULL,
+	status TEXT NOT NULL,
+	PRIMARY KEY (block_number, event_index)
+);
+CREATE TABLE IF NOT EXISTS trades (
+	block_number INTEGER NOT NULL,
+	event_index INTEGER NOT NULL,
+	extrinsic_index INTEGER,
+	side TEXT NOT NULL,
+	account TEXT NOT NULL,
+	proposal_id INTEGER NOT NULL,
+	currency_id INTEGER NOT NULL,
+	number TEXT NOT NULL,
+	PRIMARY KEY (block_number, event_index)
+);
+CREATE TABLE IF NOT EXISTS liquidity (
+	block_number INTEGER NOT NULL,
+	event_index INTEGER NOT NULL,
+	extrinsic_index INTEGER,
+	kind TEXT NOT NULL,
+	account TEXT NOT NULL,
+	proposal_id INTEGER NOT NULL,
+	currency_id INTEGER NOT NULL,
+	number TEXT NOT NULL,
+	PRIMARY KEY (block_number, event_index)
+);
+CREATE TABLE IF NOT EXISTS token_movements (
+	block_number INTEGER NOT NULL,
+	event_index INTEGER NOT NULL,
+	extrinsic_index INTEGER,
+	kind TEXT NOT NULL,
+	currency_id INTEGER NOT NULL,
+	operator TEXT,
+	from_account TEXT,
+	to_account TEXT,
+	number TEXT NOT NULL,
+	PRIMARY KEY (block_number, event_index)
+);
+CREATE TABLE IF NOT EXISTS autonomy_actions (
+	block_number INTEGER NOT NULL,
+	event_index INTEGER NOT NULL,
+	extrinsic_index INTEGER,
+	kind TEXT NOT NULL,
+	account TEXT NOT NULL,
+	proposal_id INTEGER,
+	target TEXT,
+	currency_id INTEGER,
+	number TEXT,
+	approve INTEGER,
+	PRIMARY KEY (block_number, event_index)
+);
+CREATE VIEW IF NOT EXISTS proposal_latest_status AS
+SELECT p.*, (
+	SELECT c.status FROM proposal_status_changes c
+	WHERE c.proposal_id = p.proposal_id
+	ORDER BY c.block_number DESC, c.event_index DESC
+	LIMIT 1
+) AS status
+FROM proposals p;
+";
+
+/// Tables whose rows are derived from the events of a block.
+const EVENT_TABLES: [&str; 6] = [
+	"proposals",
+	"proposal_status_changes",
+	"trades",
+	"liquidity",
+	"token_movements",
+	"autonomy_actions",
+];
+
+pub struct IndexDb {
+	conn: Connection,
+}
+
+impl IndexDb {
+	pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+		let conn = Connection::open(path)?;
+		conn.execute_batch(SCHEMA)?;
+		Ok(IndexDb { conn })
+	}
+
+	pub fn last_block(&self) -> Result<Option<BlockNumber>> {
+		let number = self
+			.conn
+			.query_row("SELECT MAX(number) FROM blocks", params![], |row| {
+				row.get(0)
+			})?;
+		Ok(number)
+	}
+
+	/// Stored blocks that can still be reorganized away, lowest first.
+	pub fn unfinalized_blocks(&self) -> Result<Vec<(BlockNumber, String)>> {
+		let mut statement = self
+			.conn
+			.prepare("SELECT number, hash FROM blocks WHERE finalized = 0 ORDER BY number")?;
+		let rows = statement.query_map(params![], |row| Ok((row.get(0)?, row.get(1)?)))?;
+		Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+	}
+
+	/// Writes a block and the rows derived from its events atomically.
+	pub fn insert_block(
+		&mut self,
+		number: BlockNumber,
+		hash: Hash,
+		finalized: bool,
+		records: &[EventRecord],
+	) -> Result<()> {
+		let tx = self.conn.transaction()?;
+		tx.execute(
+			"INSERT INTO blocks (number, hash, finalized) VALUES (?1, ?2, ?3)",
+			params![number, format!("{:?}", hash), finalized],
+		)?;
+		for record in records {
+			insert_event(&tx, record)?;
+		}
+		tx.commit()?;
+		Ok(())
+	}
+
+	/// Drops block `from` and everything after it.
+	pub fn rollback(&mut self, from: BlockNumber) -> Result<()> {
+		let tx = self.conn.transaction()?;
+		for table in EVENT_TABLES.iter() {
+			tx.execute(
+				&format!("DELETE FROM {} WHERE block_number >= ?1", table),
+				params![from],
+			)?;
+		}
+		tx.execute("DELETE FROM blocks WHERE number >= ?1", params![from])?;
+		tx.commit()?;
+		Ok(())
+	}
+
+	/// Rolls back from the first unfinalized block whose stored hash differs
+	/// from `canonical`, the best chain's hash at each unfinalized number,
+	/// and returns that block.
+	pub fn rollback_reorganized(
+		&mut self,
+		canonical: &[(BlockNumber, Option<Hash>)],
+	) -> Result<Option<BlockNumber>> {
+		for (number, stored) in self.unfinalized_blocks()? {
+			let current = canonical
+				.iter()
+				.find(|(canonical, _)| *canonical == number)
+				.and_then(|(_, hash)| *hash);
+			if current.map(|hash| format!("{:?}", hash)) != Some(stored) {
+				self.rollback(number)?;
+				return Ok(Some(number));
+			}
+		}
+		Ok(None)
+	}
+
+	pub fn finalize(&mut self, up_to: BlockNumber) -> Result<()> {
+		self.conn.execute(
+			"UPDATE blocks SET finalized = 1 WHERE number <= ?1 AND finalized = 0",
+			params![up_to],
+		)?;
+		Ok(())
+	}
+}
+
+fn insert_event(tx: &Transaction, record: &EventRecord) -> Result<()> {
+	let position = (
+		record.block_number,
+		record.event_index,
+		record.extrinsic_index,
+	);
+	match &record.event {
+		XPredictEvent::NewProposal(event) => {
+			tx.execute(
+				"INSERT INTO proposals
+				(proposal_id, creator, currency_id, block_number, extrinsic_index)
+				VALUES (?1, ?2, ?3, ?4, ?5)",
+				params![
+					event.proposal_id,
+					event.who.to_string(),
+					event.currency_id,
+					record.block_number,
+					record.extrinsic_index
+				],
+			)?;
+		}
+		XPredictEvent::ProposalStatusChanged(event) => {
+			tx.execute(
+				"INSERT INTO proposal_status_changes
+				(block_number, event_index, extrinsic_index, proposal_id, status)
+				VALUES (?1, ?2, ?3, ?4, ?5)",
+				params![
+					position.0,
+					position.1,
+					position.2,
+					event.proposal_id,
+					format!("{:?}", event.state)
+				],
+			)?;
+		}
+		XPredictEvent::Buy(event) => trade(
+			tx,
+			position,
+			"buy",
+			&event.from,
+			event.proposal_id,
+			event.currency_id,
+			event.number,
+		)?,
+		XPredictEvent::Sell(event) => trade(
+			tx,
+			position,
+			"sell",
+			&event.from,
+			event.proposal_id,
+			event.currency_id,
+			event.number,
+		)?,
+		XPredictEvent::Retrieval(event) => trade(
+			tx,
+			position,
+			"retrieval",
+			&event.from,
+			event.proposal_id,
+			event.currency_id,
+			event.number,
+		)?,
+		XPredictEvent::AddLiquidity(event) => liquidity(
+			tx,
+			position,
+			"add",
+			&event.from,
+			event.proposal_id,
+			event.currency_id,
+			event.number,
+		)?,
+		XPredictEvent::RemoveLiquidity(event) => liquidity(
+			tx,
+			position,
+			"remove",
+			&event.from,
+			event.proposal_id,
+			event.currency_id,
+			event.number,
+		)?,
+		XPredictEvent::Mint(event) => token_movement(
+			tx,
+			position,
+			"mint",
+			event.currency_id,
+			None,
+			None,
+			Some(&event.to),
+			event.number,
+		)?,
+		XPredictEvent::Burn(event) => token_movement(
+			tx,
+			position,
+			"burn",
+			event.currency_id,
+			None,
+			Some(&event.to),
+			None,
+			event.number,
+		)?,
+		XPredictEvent::BurnFrom(event) => token_movement(
+			tx,
+			position,
+			"burn_from",
+			event.currency_id,
+			Some(&event.to),
+			Some(&event.from),
+			None,
+			event.number,
+		)?,
+		XPredictEvent::Transfer(event) => token_movement(
+			tx,
+			position,
+			"transfer",
+			event.currency_id,
+			None,
+			Some(&event.from),
+			Some(&event.to),
+			event.number,
+		)?,
+		XPredictEvent::TransferFrom(event) => token_movement(
+			tx,
+			position,
+			"transfer_from",
+			event.currency_id,
+			Some(&event.who),
+			Some(&event.from),
+			Some(&event.to),
+			event.number,
+		)?,
+		XPredictEvent::Stake(event) => autonomy_action(
+			tx,
+			position,
+			"stake",
+			&event.from,
+			AutonomyDetails {
+				number: Some(event.number),
+				..Default::default()
+			},
+		)?,
+		XPredictEvent::UnStake(event) => autonomy_action(
+			tx,
+			position,
+			"unstake",
+			&event.from,
+			AutonomyDetails {
+				number: Some(event.number),
+				..Default::default()
+			},
+		)?,
+		XPredictEvent::Slash(event) => autonomy_action(
+			tx,
+			position,
+			"slash",
+			&event.who,
+			AutonomyDetails {
+				number: Some(event.number),
+				..Default::default()
+			},
+		)?,
+		XPredictEvent::Tagging(event) => {
+			autonomy_action(tx, position, "tag", &event.who, Default::default())?
+		}
+		XPredictEvent::UnTagging(event) => {
+			autonomy_action(tx, position, "untag", &event.who, Default::default())?
+		}
+		XPredictEvent::UploadResult(event) => autonomy_action(
+			tx,
+			position,
+			"upload_result",
+			&event.who,
+			AutonomyDetails {
+				proposal_id: Some(event.proposal_id),
+				currency_id: Some(event.currency_id),
+				..Default::default()
+			},
+		)?,
+		XPredictEvent::Report(event) => autonomy_action(
+			tx,
+			position,
+			"report",
+			&event.from,
+			AutonomyDetails {
+				proposal_id: Some(event.proposal_id),
+				target: Some(&event.to),
+				number: Some(event.number),
+				..Default::default()
+			},
+		)?,
+		XPredictEvent::SecondedReport(event) => autonomy_action(
+			tx,
+			position,
+			"seconded_report",
+			&event.from,
+			AutonomyDetails {
+				proposal_id: Some(event.proposal_id),
+				target: Some(&event.to),
+				approve: Some(event.approve),
+				..Default::default()
+			},
+		)?,
+		XPredictEvent::TakeOut(event) => autonomy_action(
+			tx,
+			position,
+			"take_out",
+			&event.from,
+			AutonomyDetails {
+				proposal_id: Some(event.proposal_id),
+				target: Some(&event.to),
+				number: Some(event.number),
+				..Default::default()
+			},
+		)?,
+		_ => {}
+	}
+	Ok(())
+}
+
+type Position = (BlockNumber, u32, Option<u32>);
+
+fn trade(
+	tx: &Transaction,
+	position: Position,
+	side: &str,
+	account: &AccountId,
+	proposal_id: ProposalId,
+	currency_id: CurrencyId,
+	number: Balance,
+) -> Result<()> {
+	tx.execute(
+		"INSERT INTO trades
+		(block_number, event_index, extrinsic_index,
+		side, account, proposal_id, currency_id, number)
+		VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+		params![
+			position.0,
+			position.1,
+			position.2,
+			side,
+			account.to_string(),
+			proposal_id,
+			currency_id,
+			number.to_string()
+		],
+	)?;
+	Ok(())
+}
+
+fn liquidity(
+	tx: &Transaction,
+	position: Position,
+	kind: &str,
+	account: &AccountId,
+	proposal_id: ProposalId,
+	currency_id: CurrencyId,
+	number: Balance,
+) -> Result<()> {
+	tx.execute(
+		"INSERT INTO liquidity
+		(block_number, event_index, extrinsic_index,
+		kind, account, proposal_id, currency_id, number)
+		VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+		params![
+			position.0,
+			position.1,
+			position.2,
+			kind,
+			account.to_string(),
+			proposal_id,
+			currency_id,
+			number.to_string()
+		],
+	)?;
+	Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn token_movement(
+	tx: &Transaction,
+	position: Position,
+	kind: &str,
+	currency_id: CurrencyId,
+	operator: Option<&AccountId>,
+	from: Option<&AccountId>,
+	to: Option<&AccountId>,
+	number: Balance,
+) -> Result<()> {
+	tx.execute(
+		"INSERT INTO token_movements
+		(block_number, event_index, extrinsic_index,
+		kind, currency_id, operator, from_account, to_account, number)
+		VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+		params![
+			position.0,
+			position.1,
+			position.2,
+			kind,
+			currency_id,
+			operator.map(ToString::to_string),
+			from.map(ToString::to_string),
+			to.map(ToString::to_string),
+			number.to_string()
+		],
+	)?;
+	Ok(())
+}
+
+#[derive(Default)]
+struct AutonomyDetails<'a> {
+	proposal_id: Option<ProposalId>,
+	target: Option<&'a AccountId>,
+	currency_id: Option<CurrencyId>,
+	number: Option<Balance>,
+	approve: Option<bool>,
+}
+
+fn autonomy_action(
+	tx: &Transaction,
+	position: Position,
+	kind: &str,
+	account: &AccountId,
+	details: AutonomyDetails,
+) -> Result<()> {
+	tx.execute(
+		"INSERT INTO autonomy_actions
+		(block_number, event_index, extrinsic_index,
+		kind, account, proposal_id, target, currency_id, number, approve)
+		VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+		params![
+			position.0,
+			position.1,
+			position.2,
+			kind,
+			account.to_string(),
+			details.proposal_id,
+			details.target.map(ToString::to_string),
+			details.currency_id,
+			details.number.map(|number| number.to_string()),
+			details.approve
+		],
+	)?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::marker::PhantomData;
+
+	use super::*;
+	use crate::pallets::{proposals::ProposalStatusChangedEvent, ProposalStatus};
+
+	fn status_change(block_number: BlockNumber, event_index: u32) -> EventRecord {
+		EventRecord {
+			block_number,
+			block_hash: Hash::repeat_byte(block_number as u8),
+			event_index,
+			extrinsic_index: Some(1),
+			event: XPredictEvent::ProposalStatusChanged(ProposalStatusChangedEvent {
+				proposal_id: 0,
+				state: ProposalStatus::FormalPrediction,
+				_runtime: PhantomData,
+			}),
+		}
+	}
+
+	fn db_with_blocks(finalized_up_to: BlockNumber) -> IndexDb {
+		let mut db = IndexDb::open(":memory:").unwrap();
+		for number in 1..=5 {
+			let records = [status_change(number, 3)];
+			let hash = Hash::repeat_byte(number as u8);
+			db.insert_block(number, hash, number <= finalized_up_to, &records)
+				.unwrap();
+		}
+		db
+	}
+
+	fn count(db: &IndexDb, sql: &str) -> u32 {
+		db.conn.query_row(sql, params![], |row| row.get(0)).unwrap()
+	}
+
+	fn canonical(db: &IndexDb, forked_from: BlockNumber) -> Vec<(BlockNumber, Option<Hash>)> {
+		db.unfinalized_blocks()
+			.unwrap()
+			.into_iter()
+			.map(|(number, _)| {
+				let byte = if number >= forked_from {
+					0xff
+				} else {
+					number as u8
+				};
+				(number, Some(Hash::repeat_byte(byte)))
+			})
+			.collect()
+	}
+
+	#[test]
+	fn keeps_blocks_on_the_best_chain() {
+		let mut db = db_with_blocks(2);
+		let canonical = canonical(&db, BlockNumber::MAX);
+		assert_eq!(db.rollback_reorganized(&canonical).unwrap(), None);
+		assert_eq!(db.last_block().unwrap(), Some(5));
+		assert_eq!(
+			count(&db, "SELECT COUNT(*) FROM proposal_status_changes"),
+			5
+		);
+	}
+
+	#[test]
+	fn rolls_back_from_the_first_reorganized_block() {
+		let mut db = db_with_blocks(2);
+		let canonical = canonical(&db, 4);
+		assert_eq!(db.rollback_reorganized(&canonical).unwrap(), Some(4));
+		assert_eq!(db.last_block().unwrap(), Some(3));
+		assert_eq!(
+			count(&db, "SELECT COUNT(*) FROM proposal_status_changes"),
+			3
+		);
+		assert_eq!(
+			count(&db, "SELECT MAX(block_number) FROM proposal_status_changes"),
+			3
+		);
+	}
+
+	#[test]
+	fn rolls_back_blocks_gone_from_the_chain() {
+		let mut db = db_with_blocks(2);
+		let canonical = [(3, Some(Hash::repeat_byte(3))), (4, None), (5, None)];
+		assert_eq!(db.rollback_reorganized(&canonical).unwrap(), Some(4));
+		assert_eq!(db.last_block().unwrap(), Some(3));
+	}
+
+	#[test]
+	fn finalized_blocks_are_never_rolled_back() {
+		let mut db = db_with_blocks(5);
+		assert_eq!(db.rollback_reorganized(&[]).unwrap(), None);
+		assert_eq!(db.last_block().unwrap(), Some(5));
+	}
+
+	#[test]
+	fn stores_the_system_event_index() {
+		let db = db_with_blocks(0);
+		assert_eq!(
+			count(&db, "SELECT MIN(event_index) FROM proposal_status_changes"),
+			3
+		);
+	}
+}
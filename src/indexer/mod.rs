@@ -0,0 +1,92 @@
+mod db;
+
+use subxt::Client;
+
+use crate::{
+	error::{Result, XPredictError},
+	events::block_events,
+	runtime::{BlockNumber, Hash, XPredictRuntime},
+};
+
+pub use db::IndexDb;
+
+/// Follows the chain into an `IndexDb`. Blocks above the finalized head are
+/// indexed too and rolled back when the chain reorganizes past them.
+pub struct Indexer<'a> {
+	client: &'a Client<XPredictRuntime>,
+	db: IndexDb,
+	start_block: BlockNumber,
+}
+
+impl<'a> Indexer<'a> {
+	/// `start_block` only applies to an empty database, otherwise indexing
+	/// resumes after the last stored block.
+	pub fn new(client: &'a Client<XPredictRuntime>, db: IndexDb, start_block: BlockNumber) -> Self {
+		Indexer {
+			client,
+			db,
+			start_block,
+		}
+	}
+
+	/// Indexes up to the current best block and returns it.
+	pub async fn sync(&mut self) -> Result<BlockNumber> {
+		self.rollback_reorganized().await?;
+
+		let finalized_hash = self.client.finalized_head().await?;
+		let finalized = self.header_number(finalized_hash).await?;
+		let best = self
+			.client
+			.header(None::<Hash>)
+			.await?
+			.ok_or(XPredictError::HeaderNotFound(None))?
+			.number;
+
+		let mut next = match self.db.last_block()? {
+			Some(last) => last + 1,
+			None => self.start_block,
+		};
+		while next <= best {
+			let hash = self
+				.client
+				.block_hash(Some(next.into()))
+				.await?
+				.ok_or(XPredictError::BlockNotFound(next))?;
+			let records = block_events(self.client, next, hash).await?;
+			self.db
+				.insert_block(next, hash, next <= finalized, &records)?;
+			next += 1;
+		}
+		self.db.finalize(finalized)?;
+		Ok(best)
+	}
+
+	/// Syncs once, then again on every new block until the subscription ends.
+	pub async fn run(&mut self) -> Result<()> {
+		let mut headers = self.client.subscribe_blocks().await?;
+		self.sync().await?;
+		while headers.next().await.map_err(subxt::Error::from)?.is_some() {
+			self.sync().await?;
+		}
+		Ok(())
+	}
+
+	/// Drops the stored blocks from the first one that is no longer on the
+	/// best chain.
+	async fn rollback_reorganized(&mut self) -> Result<()> {
+		let mut canonical = Vec::new();
+		for (number, _) in self.db.unfinalized_blocks()? {
+			canonical.push((number, self.client.block_hash(Some(number.into())).await?));
+		}
+		self.db.rollback_reorganized(&canonical)?;
+		Ok(())
+	}
+
+	async fn header_number(&self, hash: Hash) -> Result<BlockNumber> {
+		self.client
+			.header(Some(hash))
+			.await?
+			.map(|header| header.number)
+			.ok_or(XPredictError::HeaderNotFound(Some(hash)))
+	}
+}
@@ -0,0 +1,71 @@
+use std::{
+	fs::{File, OpenOptions},
+	io::{BufRead, BufReader, Write},
+	path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::KeeperAction;
+use crate::{
+	error::{Result, XPredictError},
+	pallets::{Moment, ProposalId, ProposalStatus},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+	/// Milliseconds since the epoch, like the chain's moments
+	pub time: Moment,
+	pub proposal_id: ProposalId,
+	/// Status the proposal was found in, `None` when it could not be read
+	pub from: Option<ProposalStatus>,
+	/// `None` when the proposal could not be read, see `error`
+	pub action: Option<KeeperAction>,
+	/// Only planned, nothing was submitted
+	pub dry_run: bool,
+	/// `None` once the action went through
+	pub error: Option<String>,
+}
+
+/// Append-only JSON lines file of the keeper's actions.
+pub struct Journal {
+	path: PathBuf,
+	file: File,
+}
+
+impl Journal {
+	pub fn open(path: &Path) -> Result<Self> {
+		let file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(path)
+			.map_err(|e| journal_error(path, e))?;
+		Ok(Journal {
+			path: path.to_path_buf(),
+			file,
+		})
+	}
+
+	pub fn append(&mut self, entry: &JournalEntry) -> Result<()> {
+		let line = serde_json::to_string(entry).map_err(|e| journal_error(&self.path, e))?;
+		writeln!(self.file, "{}", line)
+			.and_then(|_| self.file.flush())
+			.map_err(|e| journal_error(&self.path, e))
+	}
+
+	/// Every entry written so far, oldest first.
+	pub fn entries(&self) -> Result<Vec<JournalEntry>> {
+		let file = File::open(&self.path).map_err(|e| journal_error(&self.path, e))?;
+		BufReader::new(file)
+			.lines()
+			.map(|line| {
+				let line = line.map_err(|e| journal_error(&self.path, e))?;
+				serde_json::from_str(&line).map_err(|e| journal_error(&self.path, e))
+			})
+			.collect()
+	}
+}
+
+fn journal_error(path: &Path, e: impl ToString) -> XPredictError {
+	XPredictError::Keeper(format!("{}: {}", path.display(), e.to_string()))
+}
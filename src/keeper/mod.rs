@@ -0,0 +1,348 @@
+//! Keeper making the proposal status transitions the chain has not made on
+//! time, as configured by `KeeperConfig`.
+
+mod journal;
+
+pub use journal::{Journal, JournalEntry};
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use subxt::Client;
+
+use crate::{
+	config::KeeperConfig,
+	error::Result,
+	logic::XPredictLogic,
+	pairs::XPredictSigner,
+	pallets::{CurrencyId, Moment, ProposalId, ProposalStatus},
+	runtime::XPredictRuntime,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeeperAction {
+	/// Sudo `SetStatusCall`
+	SetStatus(ProposalStatus),
+	/// Sudo `SetResultCall` with the winning token
+	SetResult(CurrencyId),
+}
+
+pub struct Keeper<'a> {
+	client: &'a Client<XPredictRuntime>,
+	signer: &'a XPredictSigner,
+	policy: KeeperConfig,
+	journal: Journal,
+	dry_run: bool,
+	/// Actions that went through, or were planned in a dry run; loaded from
+	/// the journal so a restart does not repeat them
+	attempted: HashSet<(ProposalId, KeeperAction)>,
+	/// Failed actions, retried once their backoff has passed
+	failures: HashMap<(ProposalId, KeeperAction), Backoff>,
+	ended: HashSet<ProposalId>,
+	/// Proposals whose last check failed, journaled once until one passes
+	unreadable: HashSet<ProposalId>,
+}
+
+impl<'a> Keeper<'a> {
+	/// Actions that went through according to `journal` are not attempted
+	/// again, planned ones only in a dry run. Failed ones are retried.
+	pub fn new(
+		client: &'a Client<XPredictRuntime>,
+		signer: &'a XPredictSigner,
+		policy: KeeperConfig,
+		journal: Journal,
+		dry_run: bool,
+	) -> Result<Self> {
+		let attempted = attempted_actions(journal.entries()?, dry_run);
+		Ok(Keeper {
+			client,
+			signer,
+			policy,
+			journal,
+			dry_run,
+			attempted,
+			failures: HashMap::new(),
+			ended: HashSet::new(),
+			unreadable: HashSet::new(),
+		})
+	}
+
+	/// Checks every proposal on each new block, journaling each action and
+	/// handing it to `on_entry`.
+	pub async fn run(&mut self, mut on_entry: impl FnMut(&JournalEntry)) -> Result<()> {
+		let mut headers = self.client.subscribe_blocks().await?;
+		self.tick(&mut on_entry).await?;
+		while headers.next().await.map_err(subxt::Error::from)?.is_some() {
+			self.tick(&mut on_entry).await?;
+		}
+		Ok(())
+	}
+
+	/// One pass over the proposals that have not ended. A proposal that
+	/// cannot be read is journaled and skipped.
+	pub async fn tick(&mut self, on_entry: &mut impl FnMut(&JournalEntry)) -> Result<()> {
+		let now = XPredictLogic::now()?;
+		for proposal_id in 0..XPredictLogic::proposal_count(self.client).await? {
+			if self.ended.contains(&proposal_id) {
+				continue;
+			}
+			let checked = self.check(proposal_id, now).await;
+			let (status, action) = match checked {
+				Ok(found) => {
+					self.unreadable.remove(&proposal_id);
+					match found {
+						Some(found) => found,
+						None => continue,
+					}
+				}
+				Err(e) => {
+					if self.unreadable.insert(proposal_id) {
+						let entry = JournalEntry {
+							time: now,
+							proposal_id,
+							from: None,
+							action: None,
+							dry_run: self.dry_run,
+							error: Some(e.to_string()),
+						};
+						self.journal.append(&entry)?;
+						on_entry(&entry);
+					}
+					continue;
+				}
+			};
+			let key = (proposal_id, action);
+			if self.attempted.contains(&key)
+				|| self
+					.failures
+					.get(&key)
+					.map_or(false, |backoff| !backoff.ready(now))
+			{
+				continue;
+			}
+			let error = if self.dry_run {
+				None
+			} else {
+				self.apply(proposal_id, action)
+					.await
+					.err()
+					.map(|e| e.to_string())
+			};
+			match error {
+				None => {
+					self.failures.remove(&key);
+					self.attempted.insert(key);
+				}
+				Some(_) => self.failures.entry(key).or_default().fail(now),
+			}
+			let entry = JournalEntry {
+				time: now,
+				proposal_id,
+				from: Some(status),
+				action: Some(action),
+				dry_run: self.dry_run,
+				error,
+			};
+			self.journal.append(&entry)?;
+			on_entry(&entry);
+		}
+		Ok(())
+	}
+
+	/// The status of a proposal and the action due for it, if any.
+	async fn check(
+		&mut self,
+		proposal_id: ProposalId,
+		now: Moment,
+	) -> Result<Option<(ProposalStatus, KeeperAction)>> {
+		let status = XPredictLogic::proposal_status(self.client, proposal_id).await?;
+		if status == ProposalStatus::End {
+			self.ended.insert(proposal_id);
+			return Ok(None);
+		}
+		let action = self.due(proposal_id, status, now).await?;
+		Ok(action.map(|action| (status, action)))
+	}
+
+	/// The action `policy` calls for, if the transition is overdue.
+	async fn due(
+		&self,
+		proposal_id: ProposalId,
+		status: ProposalStatus,
+		now: Moment,
+	) -> Result<Option<KeeperAction>> {
+		let client = self.client;
+		let overdue = |deadline: Moment| {
+			now >= deadline.saturating_add(self.policy.grace_secs.saturating_mul(1000))
+		};
+		let action = match status {
+			ProposalStatus::OriginalPrediction if self.policy.promote_voted => {
+				XPredictLogic::proposal_vote_reached(client, proposal_id)
+					.await?
+					.then(|| KeeperAction::SetStatus(ProposalStatus::FormalPrediction))
+			}
+			ProposalStatus::FormalPrediction if self.policy.close => {
+				let close_time = XPredictLogic::proposal_close_time(client, proposal_id).await?;
+				overdue(close_time)
+					.then(|| KeeperAction::SetStatus(ProposalStatus::WaitingForResults))
+			}
+			ProposalStatus::WaitingForResults if self.policy.announce => {
+				let close_time = XPredictLogic::proposal_close_time(client, proposal_id).await?;
+				let publicity = XPredictLogic::autonomy_publicity_interval(client).await?;
+				overdue(close_time.saturating_add(publicity))
+					.then(|| KeeperAction::SetStatus(ProposalStatus::ResultAnnouncement))
+			}
+			ProposalStatus::ResultAnnouncement if self.policy.resolve => {
+				let announced =
+					XPredictLogic::proposal_announcement_time(client, proposal_id).await?;
+				let interval = XPredictLogic::autonomy_report_interval(client).await?;
+				match announced {
+					Some(time) if overdue(time.saturating_add(interval)) => {
+						self.winner(proposal_id).await?.map(KeeperAction::SetResult)
+					}
+					_ => None,
+				}
+			}
+			_ => None,
+		};
+		Ok(action)
+	}
+
+	/// The token with the most uploaded stake, `None` on a tie.
+	async fn winner(&self, proposal_id: ProposalId) -> Result<Option<CurrencyId>> {
+		let (yes, no) = XPredictLogic::proposal_pairs(self.client, proposal_id).await?;
+		let yes_stake =
+			XPredictLogic::autonomy_statistical_result(self.client, proposal_id, yes).await?;
+		let no_stake =
+			XPredictLogic::autonomy_statistical_result(self.client, proposal_id, no).await?;
+		Ok(match yes_stake.cmp(&no_stake) {
+			std::cmp::Ordering::Greater => Some(yes),
+			std::cmp::Ordering::Less => Some(no),
+			std::cmp::Ordering::Equal => None,
+		})
+	}
+
+	async fn apply(&self, proposal_id: ProposalId, action: KeeperAction) -> Result<()> {
+		match action {
+			KeeperAction::SetStatus(status) => {
+				XPredictLogic::proposal_set_status(self.client, self.signer, proposal_id, status)
+					.await
+			}
+			KeeperAction::SetResult(currency_id) => XPredictLogic::proposal_set_result(
+				self.client,
+				self.signer,
+				proposal_id,
+				currency_id,
+			)
+			.await
+			.map(drop),
+		}
+	}
+}
+
+/// The actions of `entries` that went through, or were planned in a dry run
+/// when `dry_run` is set.
+fn attempted_actions(
+	entries: Vec<JournalEntry>,
+	dry_run: bool,
+) -> HashSet<(ProposalId, KeeperAction)> {
+	entries
+		.into_iter()
+		.filter(|entry| entry.dry_run == dry_run && entry.error.is_none())
+		.filter_map(|entry| Some((entry.proposal_id, entry.action?)))
+		.collect()
+}
+
+/// First delay before retrying a failed action, in milliseconds
+const RETRY_DELAY: Moment = 6_000;
+/// Longest delay between retries, in milliseconds
+const MAX_RETRY_DELAY: Moment = 10 * 60_000;
+
+/// Exponential backoff of a failed action.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Backoff {
+	failures: u32,
+	retry_at: Moment,
+}
+
+impl Backoff {
+	fn fail(&mut self, now: Moment) {
+		let delay = RETRY_DELAY
+			.saturating_mul(1 << self.failures.min(16))
+			.min(MAX_RETRY_DELAY);
+		self.failures = self.failures.saturating_add(1);
+		self.retry_at = now.saturating_add(delay);
+	}
+
+	fn ready(&self, now: Moment) -> bool {
+		now >= self.retry_at
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entry(proposal_id: ProposalId, dry_run: bool, error: Option<&str>) -> JournalEntry {
+		JournalEntry {
+			time: 0,
+			proposal_id,
+			from: Some(ProposalStatus::FormalPrediction),
+			action: Some(KeeperAction::SetStatus(ProposalStatus::WaitingForResults)),
+			dry_run,
+			error: error.map(Into::into),
+		}
+	}
+
+	#[test]
+	fn failed_entries_are_retried() {
+		let close = KeeperAction::SetStatus(ProposalStatus::WaitingForResults);
+		let entries = vec![
+			entry(1, false, None),
+			entry(2, false, Some("rpc error")),
+			entry(3, true, None),
+			JournalEntry {
+				action: None,
+				..entry(4, false, Some("unreadable"))
+			},
+		];
+		let attempted = attempted_actions(entries.clone(), false);
+		assert_eq!(attempted, [(1, close)].iter().copied().collect());
+		let planned = attempted_actions(entries, true);
+		assert_eq!(planned, [(3, close)].iter().copied().collect());
+	}
+
+	#[test]
+	fn failed_entries_survive_a_restart() {
+		let path = std::env::temp_dir().join(format!(
+			"xpredict-keeper-journal-{}.jsonl",
+			std::process::id()
+		));
+		let _ = std::fs::remove_file(&path);
+		let mut journal = Journal::open(&path).unwrap();
+		journal.append(&entry(1, false, Some("rpc error"))).unwrap();
+		journal.append(&entry(2, false, None)).unwrap();
+		let attempted = attempted_actions(Journal::open(&path).unwrap().entries().unwrap(), false);
+		std::fs::remove_file(&path).unwrap();
+		let close = KeeperAction::SetStatus(ProposalStatus::WaitingForResults);
+		assert!(!attempted.contains(&(1, close)));
+		assert!(attempted.contains(&(2, close)));
+	}
+
+	#[test]
+	fn backs_off_failed_actions() {
+		let mut backoff = Backoff::default();
+		assert!(backoff.ready(0));
+		backoff.fail(1_000);
+		assert!(!backoff.ready(1_000 + RETRY_DELAY - 1));
+		assert!(backoff.ready(1_000 + RETRY_DELAY));
+		backoff.fail(10_000);
+		assert!(!backoff.ready(10_000 + RETRY_DELAY));
+		assert!(backoff.ready(10_000 + 2 * RETRY_DELAY));
+		for _ in 0..40 {
+			backoff.fail(0);
+		}
+		assert_eq!(backoff.retry_at, MAX_RETRY_DELAY);
+	}
+}
@@ -0,0 +1,271 @@
+use std::{
+	fs::{self, OpenOptions},
+	io::Write,
+	path::{Path, PathBuf},
+	slice::Iter,
+};
+
+use sp_runtime::traits::{IdentifyAccount, Verify};
+use subxt::{sp_core::sr25519::Pair as Sr25519Pair, PairSigner, Runtime};
+
+use super::{JsonKeyFile, SchemePair};
+use crate::{
+	error::{Result, XPredictError},
+	pairs::{PairAuthority, XPredictKeystore, XPredictPairs},
+};
+
+const AUTHORITIES: [(PairAuthority, &str); 2] = [
+	(PairAuthority::Admin, "admin"),
+	(PairAuthority::Normal, "normal"),
+];
+
+/// Keys encrypted at rest under `<path>/<authority>/<address>.json`, in the
+/// polkadot-js export format. Nothing can sign until `unlock` succeeded, and
+/// the decrypted pairs are zeroized when dropped or on `lock`. Files of other
+/// key schemes than `P` are listed but left locked.
+pub struct EncryptedKeystore<P: SchemePair = Sr25519Pair> {
+	path: PathBuf,
+	unlocked: XPredictKeystore<P>,
+}
+
+impl<P: SchemePair> EncryptedKeystore<P> {
+	pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+		let path = path.into();
+		for (_, dir) in AUTHORITIES.iter() {
+			fs::create_dir_all(path.join(dir)).map_err(|e| io_error(&path, e))?;
+		}
+		Ok(EncryptedKeystore {
+			path,
+			unlocked: XPredictKeystore::default(),
+		})
+	}
+
+	pub fn add(&self, authority: PairAuthority, pair: &P, password: &str) -> Result<PathBuf> {
+		let mut key_file = JsonKeyFile::encrypt(pair, password)?;
+		key_file
+			.meta
+			.insert("name".into(), authority_dir(authority).into());
+		let path = self
+			.path
+			.join(authority_dir(authority))
+			.join(format!("{}.json", key_file.address));
+		let content = serde_json::to_vec_pretty(&key_file)
+			.map_err(|e| XPredictError::Keystore(e.to_string()))?;
+
+		let mut options = OpenOptions::new();
+		options.write(true).create_new(true);
+		#[cfg(unix)]
+		std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+		options
+			.open(&path)
+			.and_then(|mut file| file.write_all(&content))
+			.map_err(|e| io_error(&path, e))?;
+		Ok(path)
+	}
+
+	/// Lists the stored addresses without decrypting them.
+	pub fn list(&self) -> Result<Vec<(PairAuthority, String, String)>> {
+		let mut accounts = Vec::new();
+		for (authority, path) in self.key_files()? {
+			let key_file = JsonKeyFile::from_file(&path)?;
+			let scheme = key_file.scheme().unwrap_or("unknown").to_string();
+			accounts.push((authority, key_file.address, scheme));
+		}
+		Ok(accounts)
+	}
+
+	pub fn unlock(&mut self, password: &str) -> Result<()> {
+		let mut unlocked = XPredictKeystore::default();
+		for (authority, path) in self.key_files()? {
+			let key_file = JsonKeyFile::from_file(&path)?;
+			if key_file.scheme() != Some(P::SCHEME.name()) {
+				continue;
+			}
+			let pair = key_file
+				.to_pair(password)
+				.map_err(|e| XPredictError::Keystore(format!("{}: {}", path.display(), e)))?;
+			unlocked.insert(authority, pair);
+		}
+		self.unlocked = unlocked;
+		Ok(())
+	}
+
+	pub fn lock(&mut self) {
+		self.unlocked = XPredictKeystore::default();
+	}
+
+	pub fn into_unlocked(self) -> XPredictKeystore<P> {
+		self.unlocked
+	}
+
+	fn key_files(&self) -> Result<Vec<(PairAuthority, PathBuf)>> {
+		let mut files = Vec::new();
+		for (authority, dir) in AUTHORITIES.iter() {
+			let dir = self.path.join(dir);
+			let mut paths = fs::read_dir(&dir)
+				.map_err(|e| io_error(&dir, e))?
+				.map(|entry| entry.map(|entry| entry.path()))
+				.collect::<std::io::Result<Vec<_>>>()
+				.map_err(|e| io_error(&dir, e))?;
+			paths.retain(|path| path.extension().map_or(false, |ext| ext == "json"));
+			paths.sort();
+			files.extend(paths.into_iter().map(|path| (*authority, path)));
+		}
+		Ok(files)
+	}
+}
+
+fn authority_dir(authority: PairAuthority) -> &'static str {
+	AUTHORITIES
+		.iter()
+		.find(|(a, _)| *a == authority)
+		.map(|(_, dir)| *dir)
+		.expect("every authority has a directory; qed")
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> XPredictError {
+	XPredictError::Keystore(format!("{}: {}", path.display(), error))
+}
+
+impl<T, P> XPredictPairs<T, P> for EncryptedKeystore<P>
+where
+	T: Runtime,
+	P: SchemePair,
+	T::Signature: From<P::Signature>,
+	<T::Signature as Verify>::Signer: From<P::Public> + IdentifyAccount<AccountId = T::AccountId>,
+{
+	fn get_pair_iter(&self, authority: PairAuthority) -> Option<Iter<P>> {
+		<XPredictKeystore<P> as XPredictPairs<T, P>>::get_pair_iter(&self.unlocked, authority)
+	}
+
+	fn get_pair_signer(&self, authority: PairAuthority, index: usize) -> Option<PairSigner<T, P>> {
+		<XPredictKeystore<P> as XPredictPairs<T, P>>::get_pair_signer(
+			&self.unlocked,
+			authority,
+			index,
+		)
+	}
+
+	fn get_default_signer(&self, authority: PairAuthority) -> Option<PairSigner<T, P>> {
+		<XPredictKeystore<P> as XPredictPairs<T, P>>::get_default_signer(&self.unlocked, authority)
+	}
+
+	fn get_public(&self, authority: PairAuthority, index: usize) -> Option<P::Public> {
+		<XPredictKeystore<P> as XPredictPairs<T, P>>::get_public(&self.unlocked, authority, index)
+	}
+
+	fn get_signer(pair: &P) -> PairSigner<T, P> {
+		<XPredictKeystore<P> as XPredictPairs<T, P>>::get_signer(pair)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use subxt::sp_core::{ed25519, Pair};
+
+	use super::*;
+
+	/// A keystore directory removed when the test is done.
+	struct TempDir(PathBuf);
+
+	impl TempDir {
+		fn new(name: &str) -> Self {
+			let path = std::env::temp_dir().join(format!(
+				"xpredict-keystore-{}-{}",
+				name,
+				std::process::id()
+			));
+			let _ = fs::remove_dir_all(&path);
+			TempDir(path)
+		}
+	}
+
+	impl Drop for TempDir {
+		fn drop(&mut self) {
+			let _ = fs::remove_dir_all(&self.0);
+		}
+	}
+
+	fn pair(uri: &str) -> Sr25519Pair {
+		Sr25519Pair::from_string(uri, None).unwrap()
+	}
+
+	fn public(
+		keystore: &XPredictKeystore,
+		authority: PairAuthority,
+		index: usize,
+	) -> Option<Vec<u8>> {
+		keystore
+			.get_pair(authority, index)
+			.map(|pair| pair.public().as_ref().to_vec())
+	}
+
+	#[test]
+	fn unlocks_the_added_keys() {
+		let dir = TempDir::new("unlock");
+		let mut keystore = EncryptedKeystore::<Sr25519Pair>::open(&dir.0).unwrap();
+		let path = keystore
+			.add(PairAuthority::Admin, &pair("//Alice"), "secret")
+			.unwrap();
+		assert!(path.starts_with(dir.0.join("admin")));
+		keystore
+			.add(PairAuthority::Normal, &pair("//Bob"), "secret")
+			.unwrap();
+
+		let listed = keystore.list().unwrap();
+		assert_eq!(listed.len(), 2);
+		assert_eq!(listed[0].0, PairAuthority::Admin);
+		assert_eq!(listed[0].1, pair("//Alice").address());
+		assert_eq!(listed[1].2, "sr25519");
+
+		keystore.unlock("secret").unwrap();
+		let unlocked = keystore.into_unlocked();
+		let alice = pair("//Alice").public().as_ref().to_vec();
+		let bob = pair("//Bob").public().as_ref().to_vec();
+		assert_eq!(public(&unlocked, PairAuthority::Admin, 0), Some(alice));
+		assert_eq!(public(&unlocked, PairAuthority::Normal, 0), Some(bob));
+	}
+
+	#[test]
+	fn wrong_password_unlocks_nothing() {
+		let dir = TempDir::new("password");
+		let mut keystore = EncryptedKeystore::<Sr25519Pair>::open(&dir.0).unwrap();
+		keystore
+			.add(PairAuthority::Admin, &pair("//Alice"), "secret")
+			.unwrap();
+		keystore.unlock("secret").unwrap();
+		assert!(matches!(
+			keystore.unlock("wrong"),
+			Err(XPredictError::Keystore(e)) if e.contains("invalid password")
+		));
+		// a failed unlock keeps the keys unlocked before it
+		assert!(public(&keystore.unlocked, PairAuthority::Admin, 0).is_some());
+		keystore.lock();
+		assert!(public(&keystore.unlocked, PairAuthority::Admin, 0).is_none());
+	}
+
+	#[test]
+	fn other_schemes_stay_locked() {
+		let dir = TempDir::new("scheme");
+		let ed25519 = EncryptedKeystore::<ed25519::Pair>::open(&dir.0).unwrap();
+		let bob = ed25519::Pair::from_string("//Bob", None).unwrap();
+		ed25519.add(PairAuthority::Normal, &bob, "secret").unwrap();
+
+		let mut sr25519 = EncryptedKeystore::<Sr25519Pair>::open(&dir.0).unwrap();
+		assert_eq!(sr25519.list().unwrap()[0].2, "ed25519");
+		sr25519.unlock("secret").unwrap();
+		assert!(public(&sr25519.into_unlocked(), PairAuthority::Normal, 0).is_none());
+	}
+
+	#[test]
+	fn keys_are_never_overwritten() {
+		let dir = TempDir::new("overwrite");
+		let keystore = EncryptedKeystore::<Sr25519Pair>::open(&dir.0).unwrap();
+		keystore
+			.add(PairAuthority::Admin, &pair("//Alice"), "secret")
+			.unwrap();
+		assert!(keystore
+			.add(PairAuthority::Admin, &pair("//Alice"), "other")
+			.is_err());
+	}
+}
@@ -0,0 +1,279 @@
+use std::{convert::TryInto, fs, path::Path};
+
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use subxt::sp_core::Pair;
+use xsalsa20poly1305::{
+	aead::{Aead, NewAead},
+	Key, Nonce, XSalsa20Poly1305,
+};
+use zeroize::Zeroizing;
+
+use super::SchemePair;
+use crate::error::{Result, XPredictError};
+
+const PKCS8_HEADER: [u8; 16] = [48, 83, 2, 1, 1, 48, 5, 6, 3, 43, 101, 112, 4, 34, 4, 32];
+const PKCS8_DIVIDER: [u8; 5] = [161, 35, 3, 33, 0];
+const SALT_LENGTH: usize = 32;
+const SCRYPT_LENGTH: usize = SALT_LENGTH + 3 * 4;
+const NONCE_LENGTH: usize = 24;
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_P: u32 = 1;
+const SCRYPT_R: u32 = 8;
+
+/// An account exported from polkadot-js, encrypted with scrypt and
+/// xsalsa20-poly1305 (encoding version 3).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonKeyFile {
+	pub encoded: String,
+	pub encoding: JsonEncoding,
+	pub address: String,
+	#[serde(default)]
+	pub meta: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonEncoding {
+	pub content: Vec<String>,
+	#[serde(rename = "type")]
+	pub kind: Vec<String>,
+	pub version: String,
+}
+
+impl JsonKeyFile {
+	pub fn from_file(path: &Path) -> Result<Self> {
+		let content = fs::read_to_string(path)
+			.map_err(|e| XPredictError::Keystore(format!("{}: {}", path.display(), e)))?;
+		serde_json::from_str(&content)
+			.map_err(|e| XPredictError::Keystore(format!("{}: {}", path.display(), e)))
+	}
+
+	/// Encrypts `pair` the way polkadot-js exports accounts, so the result can
+	/// be imported there as well.
+	pub fn encrypt<P: SchemePair>(pair: &P, password: &str) -> Result<Self> {
+		let secret = pair.to_json_secret();
+		let public = pair.public();
+		let mut plain = Zeroizing::new(Vec::with_capacity(
+			PKCS8_HEADER.len() + secret.len() + PKCS8_DIVIDER.len() + public.as_ref().len(),
+		));
+		plain.extend_from_slice(&PKCS8_HEADER);
+		plain.extend_from_slice(&secret);
+		plain.extend_from_slice(&PKCS8_DIVIDER);
+		plain.extend_from_slice(public.as_ref());
+
+		let mut data = vec![0u8; SCRYPT_LENGTH + NONCE_LENGTH];
+		OsRng.fill_bytes(&mut data[..SALT_LENGTH]);
+		data[SALT_LENGTH..SALT_LENGTH + 4].copy_from_slice(&(1u32 << SCRYPT_LOG_N).to_le_bytes());
+		data[SALT_LENGTH + 4..SALT_LENGTH + 8].copy_from_slice(&SCRYPT_P.to_le_bytes());
+		data[SALT_LENGTH + 8..SCRYPT_LENGTH].copy_from_slice(&SCRYPT_R.to_le_bytes());
+		OsRng.fill_bytes(&mut data[SCRYPT_LENGTH..]);
+
+		let key = scrypt_key(password, &data[..SCRYPT_LENGTH])?;
+		let cipher = XSalsa20Poly1305::new(Key::from_slice(&key[..32]));
+		let encrypted = cipher
+			.encrypt(Nonce::from_slice(&data[SCRYPT_LENGTH..]), &plain[..])
+			.map_err(|_| XPredictError::Keystore("encryption failed".into()))?;
+		data.extend_from_slice(&encrypted);
+
+		Ok(JsonKeyFile {
+			encoded: base64::encode(&data),
+			encoding: JsonEncoding {
+				content: vec!["pkcs8".into(), P::SCHEME.name().into()],
+				kind: vec!["scrypt".into(), "xsalsa20-poly1305".into()],
+				version: "3".into(),
+			},
+			address: pair.address(),
+			meta: Map::new(),
+		})
+	}
+
+	pub fn scheme(&self) -> Option<&str> {
+		self.encoding.content.get(1).map(String::as_str)
+	}
+
+	pub fn to_pair<P: SchemePair>(&self, password: &str) -> Result<P> {
+		if self.scheme() != Some(P::SCHEME.name()) {
+			return Err(XPredictError::Keystore(format!(
+				"{}: expected a {} key, found {:?}",
+				self.address,
+				P::SCHEME.name(),
+				self.encoding.content
+			)));
+		}
+		let plain = self.decrypt(password)?;
+		let (secret, public) = split_pkcs8(&plain, P::SECRET_LENGTH)?;
+		let pair = P::from_json_secret(secret)?;
+		if pair.public().as_ref() != public {
+			return Err(XPredictError::Keystore(format!(
+				"{}: public key does not match the secret",
+				self.address
+			)));
+		}
+		Ok(pair)
+	}
+
+	fn decrypt(&self, password: &str) -> Result<Zeroizing<Vec<u8>>> {
+		if self.encoding.version != "3" || !self.encoding.kind.iter().any(|k| k == "scrypt") {
+			return Err(XPredictError::Keystore(format!(
+				"{}: unsupported encoding {:?} version {}",
+				self.address, self.encoding.kind, self.encoding.version
+			)));
+		}
+		let data = base64::decode(&self.encoded)
+			.map_err(|e| XPredictError::Keystore(format!("{}: {}", self.address, e)))?;
+		if data.len() < SCRYPT_LENGTH + NONCE_LENGTH {
+			return Err(XPredictError::Keystore(format!(
+				"{}: encoded data too short",
+				self.address
+			)));
+		}
+		let key = scrypt_key(password, &data[..SCRYPT_LENGTH])?;
+		let nonce = &data[SCRYPT_LENGTH..SCRYPT_LENGTH + NONCE_LENGTH];
+		let cipher = XSalsa20Poly1305::new(Key::from_slice(&key[..32]));
+		cipher
+			.decrypt(
+				Nonce::from_slice(nonce),
+				&data[SCRYPT_LENGTH + NONCE_LENGTH..],
+			)
+			.map(Zeroizing::new)
+			.map_err(|_| XPredictError::Keystore(format!("{}: invalid password", self.address)))
+	}
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+	u32::from_le_bytes(bytes.try_into().expect("slice of four bytes; qed"))
+}
+
+fn scrypt_key(password: &str, header: &[u8]) -> Result<Zeroizing<[u8; 64]>> {
+	let salt = &header[..SALT_LENGTH];
+	let n = read_u32(&header[SALT_LENGTH..SALT_LENGTH + 4]);
+	let p = read_u32(&header[SALT_LENGTH + 4..SALT_LENGTH + 8]);
+	let r = read_u32(&header[SALT_LENGTH + 8..SALT_LENGTH + 12]);
+	if !n.is_power_of_two() {
+		return Err(XPredictError::Keystore(format!("invalid scrypt N {}", n)));
+	}
+	let params = scrypt::Params::new(n.trailing_zeros() as u8, r, p)
+		.map_err(|e| XPredictError::Keystore(format!("invalid scrypt params: {}", e)))?;
+	let mut key = Zeroizing::new([0u8; 64]);
+	scrypt::scrypt(password.as_bytes(), salt, &params, &mut key[..])
+		.map_err(|e| XPredictError::Keystore(format!("scrypt: {}", e)))?;
+	Ok(key)
+}
+
+fn split_pkcs8(plain: &[u8], secret_length: usize) -> Result<(&[u8], &[u8])> {
+	let secret_start = PKCS8_HEADER.len();
+	let divider_start = secret_start + secret_length;
+	let public_start = divider_start + PKCS8_DIVIDER.len();
+	if plain.len() <= public_start
+		|| plain[..secret_start] != PKCS8_HEADER
+		|| plain[divider_start..public_start] != PKCS8_DIVIDER
+	{
+		return Err(XPredictError::Keystore("invalid pkcs8 key data".into()));
+	}
+	Ok((&plain[secret_start..divider_start], &plain[public_start..]))
+}
+
+#[cfg(test)]
+mod tests {
+	use subxt::sp_core::{ecdsa, ed25519, sr25519};
+
+	use super::*;
+
+	/// The RFC 8032 test 1 ed25519 key exported with password
+	/// `correct horse`, a fixed salt and nonce, and N = 2^15, p = 1, r = 8,
+	/// encrypted with libsodium's `crypto_secretbox_easy`.
+	fn known_export() -> JsonKeyFile {
+		serde_json::from_value(serde_json::json!({
+			"encoded": "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8AgAAAAQAAAAgAAABkZWZnaGlqa2xtbm9w\
+				cXJzdHV2d3h5ensZd1PZSn25/LtrmXIFl9z6hjzQpEKucXDsH/4fXDrMwu+do4kRei+U9+7yN6lDdSx7\
+				fzTvGDqCOnoceF4lzjzMSWEMJPaxjv3rxrn+ahQm+8dY12MYnouh+P/sKAdI1jkxrI4Tyj/2oa2PgUQD\
+				k5iHw2ri9+sKLmNKldd0TgZIOqNhHPNg",
+			"encoding": {
+				"content": ["pkcs8", "ed25519"],
+				"type": ["scrypt", "xsalsa20-poly1305"],
+				"version": "3"
+			},
+			"address": "5Gw54ghuAHodDGAS91DUxqvKa6PeT9bhDdns3ztBupY8pSyn",
+			"meta": { "name": "rfc8032" }
+		}))
+		.unwrap()
+	}
+
+	fn is_keystore_error<T>(result: Result<T>, message: &str) -> bool {
+		matches!(result, Err(XPredictError::Keystore(e)) if e.contains(message))
+	}
+
+	#[test]
+	fn decrypts_a_known_export() {
+		let pair: ed25519::Pair = known_export().to_pair("correct horse").unwrap();
+		assert_eq!(
+			hex::encode(pair.seed()),
+			"9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
+		);
+		assert_eq!(
+			hex::encode(pair.public()),
+			"d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+		);
+		assert_eq!(pair.address(), known_export().address);
+	}
+
+	#[test]
+	fn rejects_a_wrong_password() {
+		let result = known_export().to_pair::<ed25519::Pair>("wrong horse");
+		assert!(is_keystore_error(result, "invalid password"));
+		let result = known_export().to_pair::<ed25519::Pair>("");
+		assert!(is_keystore_error(result, "invalid password"));
+	}
+
+	#[test]
+	fn rejects_tampered_data() {
+		let mut export = known_export();
+		let mut data = base64::decode(&export.encoded).unwrap();
+		*data.last_mut().unwrap() ^= 1;
+		export.encoded = base64::encode(&data);
+		assert!(is_keystore_error(
+			export.to_pair::<ed25519::Pair>("correct horse"),
+			"invalid password"
+		));
+		export.encoded = base64::encode(&data[..SCRYPT_LENGTH]);
+		assert!(is_keystore_error(
+			export.to_pair::<ed25519::Pair>("correct horse"),
+			"too short"
+		));
+	}
+
+	#[test]
+	fn rejects_another_scheme() {
+		let result = known_export().to_pair::<sr25519::Pair>("correct horse");
+		assert!(is_keystore_error(result, "expected a sr25519 key"));
+	}
+
+	fn round_trip<P: SchemePair>(uri: &str) {
+		let pair = P::from_string(uri, None).unwrap();
+		let export = JsonKeyFile::encrypt(&pair, "secret").unwrap();
+		assert_eq!(export.scheme(), Some(P::SCHEME.name()));
+		assert_eq!(export.address, pair.address());
+		let decrypted: P = export.to_pair("secret").unwrap();
+		assert_eq!(decrypted.public().as_ref(), pair.public().as_ref());
+		assert!(is_keystore_error(
+			export.to_pair::<P>("Secret"),
+			"invalid password"
+		));
+	}
+
+	#[test]
+	fn round_trips_every_scheme() {
+		round_trip::<sr25519::Pair>("//Alice");
+		round_trip::<ed25519::Pair>("//Bob");
+		round_trip::<ecdsa::Pair>("//Charlie");
+	}
+
+	#[test]
+	fn encrypts_with_fresh_salt_and_nonce() {
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let first = JsonKeyFile::encrypt(&pair, "secret").unwrap();
+		let second = JsonKeyFile::encrypt(&pair, "secret").unwrap();
+		assert_ne!(first.encoded, second.encoded);
+	}
+}
@@ -0,0 +1,75 @@
+mod encrypted;
+mod json;
+mod scheme;
+
+use std::{env, path::PathBuf};
+
+use serde::Deserialize;
+use zeroize::Zeroizing;
+
+use crate::error::{Result, XPredictError};
+
+pub use encrypted::EncryptedKeystore;
+pub use json::JsonKeyFile;
+pub use scheme::{KeyScheme, SchemePair};
+
+/// Where the secret of a keypair comes from. A bare string is a secret URI,
+/// which covers dev accounts (`//Alice`), mnemonics with derivation paths
+/// (`<phrase>//hard/soft///password`) and `0x` prefixed seeds.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum KeySource {
+	Uri(String),
+	Mnemonic {
+		mnemonic: String,
+		#[serde(default)]
+		password: Option<String>,
+	},
+	Seed {
+		seed: String,
+	},
+	Json {
+		json: PathBuf,
+		/// Environment variable holding the export password
+		#[serde(default)]
+		password_env: Option<String>,
+		#[serde(default)]
+		password: Option<String>,
+	},
+}
+
+impl KeySource {
+	pub fn load<P: SchemePair>(&self) -> Result<P> {
+		match self {
+			KeySource::Uri(uri) => P::from_string(uri, None)
+				.map_err(|e| XPredictError::Keystore(format!("invalid secret uri: {:?}", e))),
+			KeySource::Mnemonic { mnemonic, password } => {
+				P::from_phrase(mnemonic, password.as_deref())
+					.map(|(pair, _)| pair)
+					.map_err(|e| XPredictError::Keystore(format!("invalid mnemonic: {:?}", e)))
+			}
+			KeySource::Seed { seed } => {
+				let seed = Zeroizing::new(
+					hex::decode(seed.trim_start_matches("0x"))
+						.map_err(|e| XPredictError::Keystore(format!("invalid seed: {}", e)))?,
+				);
+				P::from_seed_slice(&seed)
+					.map_err(|e| XPredictError::Keystore(format!("invalid seed: {:?}", e)))
+			}
+			KeySource::Json {
+				json,
+				password_env,
+				password,
+			} => {
+				let password = match (password_env, password) {
+					(Some(name), _) => Zeroizing::new(env::var(name).map_err(|_| {
+						XPredictError::Keystore(format!("password variable {} is not set", name))
+					})?),
+					(None, Some(password)) => Zeroizing::new(password.clone()),
+					(None, None) => Zeroizing::new(String::new()),
+				};
+				JsonKeyFile::from_file(json)?.to_pair(&password)
+			}
+		}
+	}
+}
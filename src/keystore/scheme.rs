@@ -0,0 +1,121 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+use sp_runtime::{traits::IdentifyAccount, MultiSigner};
+use subxt::sp_core::{crypto::Ss58Codec, ecdsa, ed25519, sr25519, Pair};
+use zeroize::Zeroizing;
+
+use crate::error::{Result, XPredictError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyScheme {
+	Sr25519,
+	Ed25519,
+	Ecdsa,
+}
+
+impl Default for KeyScheme {
+	fn default() -> Self {
+		KeyScheme::Sr25519
+	}
+}
+
+impl KeyScheme {
+	pub fn name(self) -> &'static str {
+		match self {
+			KeyScheme::Sr25519 => "sr25519",
+			KeyScheme::Ed25519 => "ed25519",
+			KeyScheme::Ecdsa => "ecdsa",
+		}
+	}
+}
+
+impl FromStr for KeyScheme {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s {
+			"sr25519" => Ok(KeyScheme::Sr25519),
+			"ed25519" => Ok(KeyScheme::Ed25519),
+			"ecdsa" => Ok(KeyScheme::Ecdsa),
+			_ => Err(format!(
+				"unknown key scheme {:?}, use sr25519, ed25519 or ecdsa",
+				s
+			)),
+		}
+	}
+}
+
+/// A key scheme accepted by the runtime's `MultiSignature`, with the secret
+/// layout polkadot-js uses in its pkcs8 exports.
+pub trait SchemePair: Pair {
+	const SCHEME: KeyScheme;
+	const SECRET_LENGTH: usize;
+
+	fn from_json_secret(secret: &[u8]) -> Result<Self>;
+	fn to_json_secret(&self) -> Zeroizing<Vec<u8>>;
+	fn address(&self) -> String;
+}
+
+fn address_of(signer: MultiSigner) -> String {
+	signer.into_account().to_ss58check()
+}
+
+impl SchemePair for sr25519::Pair {
+	const SCHEME: KeyScheme = KeyScheme::Sr25519;
+	const SECRET_LENGTH: usize = 64;
+
+	fn from_json_secret(secret: &[u8]) -> Result<Self> {
+		schnorrkel::SecretKey::from_ed25519_bytes(secret)
+			.map(Self::from)
+			.map_err(|e| XPredictError::Keystore(format!("invalid sr25519 secret: {}", e)))
+	}
+
+	fn to_json_secret(&self) -> Zeroizing<Vec<u8>> {
+		let keypair: &schnorrkel::Keypair = self.as_ref();
+		Zeroizing::new(keypair.secret.to_ed25519_bytes().to_vec())
+	}
+
+	fn address(&self) -> String {
+		address_of(self.public().into())
+	}
+}
+
+impl SchemePair for ed25519::Pair {
+	const SCHEME: KeyScheme = KeyScheme::Ed25519;
+	const SECRET_LENGTH: usize = 64;
+
+	fn from_json_secret(secret: &[u8]) -> Result<Self> {
+		Self::from_seed_slice(&secret[..32])
+			.map_err(|e| XPredictError::Keystore(format!("invalid ed25519 secret: {:?}", e)))
+	}
+
+	fn to_json_secret(&self) -> Zeroizing<Vec<u8>> {
+		let mut secret = Zeroizing::new(self.to_raw_vec());
+		secret.extend_from_slice(self.public().as_ref());
+		secret
+	}
+
+	fn address(&self) -> String {
+		address_of(self.public().into())
+	}
+}
+
+impl SchemePair for ecdsa::Pair {
+	const SCHEME: KeyScheme = KeyScheme::Ecdsa;
+	const SECRET_LENGTH: usize = 32;
+
+	fn from_json_secret(secret: &[u8]) -> Result<Self> {
+		Self::from_seed_slice(secret)
+			.map_err(|e| XPredictError::Keystore(format!("invalid ecdsa secret: {:?}", e)))
+	}
+
+	fn to_json_secret(&self) -> Zeroizing<Vec<u8>> {
+		Zeroizing::new(self.to_raw_vec())
+	}
+
+	fn address(&self) -> String {
+		address_of(self.public().into())
+	}
+}
@@ -0,0 +1,23 @@
+pub mod amm;
+pub mod claimer;
+pub mod config;
+pub mod dispatch;
+pub mod error;
+pub mod events;
+pub mod indexer;
+pub mod keeper;
+pub mod keystore;
+pub mod listing;
+pub mod logic;
+pub mod maker;
+pub mod oracle;
+pub mod pairs;
+pub mod pallets;
+pub mod portfolio;
+pub mod runtime;
+pub mod scenario;
+
+pub use error::{Result, XPredictError};
+pub use logic::XPredictLogic;
+pub use pairs::{PairAuthority, XPredictKeystore, XPredictPairs, XPredictSigner};
+pub use runtime::XPredictRuntime;
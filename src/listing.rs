@@ -0,0 +1,333 @@
+//! Paginated listing of storage maps through `state_getKeysPaged`, decoded
+//! into typed rows.
+
+use std::future::Future;
+
+use codec::Decode;
+use serde_json::{json, Value};
+use sp_core::{
+	hashing::{blake2_128, twox_64},
+	storage::StorageKey,
+};
+use subxt::{system::System, Client, Store};
+
+use crate::{
+	error::{Result, XPredictError},
+	logic::XPredictLogic,
+	pallets::{
+		autonomy::{AutonomyAccountStore, StakedAccountStore},
+		proposals::ProposalStatusStore,
+		tokens::{CurrenciesStore, FreeBalanceOfStore, PRC20},
+		Balance, CategoryId, CurrencyId, Moment, ProposalId, ProposalStatus,
+	},
+	runtime::XPredictRuntime,
+};
+
+type AccountId = <XPredictRuntime as System>::AccountId;
+type FreeBalances = FreeBalanceOfStore<'static, XPredictRuntime>;
+type Stakes = StakedAccountStore<'static, XPredictRuntime>;
+type Uploaders = AutonomyAccountStore<'static, XPredictRuntime>;
+
+/// Bytes of `twox128(module) ++ twox128(field)` before the map keys.
+const PREFIX_LENGTH: usize = 32;
+
+/// Rows of one page and the key to start the next one from, `None` on the
+/// last page.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+	pub rows: Vec<T>,
+	pub next: Option<StorageKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposalRow {
+	pub proposal_id: ProposalId,
+	pub status: ProposalStatus,
+	pub title: String,
+	pub category_id: CategoryId,
+	pub close_time: Moment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrencyRow {
+	pub currency_id: CurrencyId,
+	pub info: PRC20,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HolderRow {
+	pub account: AccountId,
+	pub currency_id: CurrencyId,
+	pub free: Balance,
+}
+
+/// A member of `AutonomyAccountStore` or `StakedAccountStore` with its stake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountRow {
+	pub account: AccountId,
+	pub staked: Balance,
+}
+
+impl ProposalRow {
+	pub fn to_json(&self) -> Value {
+		json!({
+			"proposal_id": self.proposal_id,
+			"status": format!("{:?}", self.status),
+			"title": self.title,
+			"category_id": self.category_id,
+			"close_time": self.close_time,
+		})
+	}
+}
+
+impl CurrencyRow {
+	pub fn to_json(&self) -> Value {
+		json!({
+			"currency_id": self.currency_id,
+			"name": String::from_utf8_lossy(&self.info.name),
+			"symbol": String::from_utf8_lossy(&self.info.symbol),
+			"decimals": self.info.decimals,
+		})
+	}
+}
+
+impl HolderRow {
+	pub fn to_json(&self) -> Value {
+		json!({
+			"account": self.account.to_string(),
+			"currency_id": self.currency_id,
+			"free": self.free.to_string(),
+		})
+	}
+}
+
+impl AccountRow {
+	pub fn to_json(&self) -> Value {
+		json!({
+			"account": self.account.to_string(),
+			"staked": self.staked.to_string(),
+		})
+	}
+}
+
+/// Proposals with an id below `CurrentProposalIdStore`.
+pub async fn proposals_page(
+	client: &Client<XPredictRuntime>,
+	count: u32,
+	start: Option<StorageKey>,
+) -> Result<Page<ProposalRow>> {
+	let current = XPredictLogic::proposal_count(client).await?;
+	let keys = client
+		.fetch_keys::<ProposalStatusStore<XPredictRuntime>>(count, start, None)
+		.await?;
+	let next = next_start(&keys, count);
+	let mut rows = Vec::with_capacity(keys.len());
+	for key in keys {
+		let proposal_id = map_key::<ProposalStatusStore<XPredictRuntime>, ProposalId>(&key)?;
+		if proposal_id >= current {
+			continue;
+		}
+		let info = XPredictLogic::proposal_info(client, proposal_id).await?;
+		rows.push(ProposalRow {
+			proposal_id,
+			status: value::<ProposalStatusStore<XPredictRuntime>>(client, key).await?,
+			title: String::from_utf8_lossy(&info.title).into_owned(),
+			category_id: info.category_id,
+			close_time: XPredictLogic::proposal_close_time(client, proposal_id).await?,
+		});
+	}
+	Ok(Page { rows, next })
+}
+
+/// Currencies with an id below `CurrentCurrencyIdStore`.
+pub async fn currencies_page(
+	client: &Client<XPredictRuntime>,
+	count: u32,
+	start: Option<StorageKey>,
+) -> Result<Page<CurrencyRow>> {
+	let current = XPredictLogic::number_of_currency(client).await?;
+	let keys = client
+		.fetch_keys::<CurrenciesStore<XPredictRuntime>>(count, start, None)
+		.await?;
+	let next = next_start(&keys, count);
+	let mut rows = Vec::with_capacity(keys.len());
+	for key in keys {
+		let currency_id = map_key::<CurrenciesStore<XPredictRuntime>, CurrencyId>(&key)?;
+		if currency_id < current {
+			rows.push(CurrencyRow {
+				currency_id,
+				info: value::<CurrenciesStore<XPredictRuntime>>(client, key).await?,
+			});
+		}
+	}
+	Ok(Page { rows, next })
+}
+
+/// Accounts holding a free balance of any non-native currency.
+pub async fn holders_page(
+	client: &Client<XPredictRuntime>,
+	count: u32,
+	start: Option<StorageKey>,
+) -> Result<Page<HolderRow>> {
+	let keys = client
+		.fetch_keys::<FreeBalances>(count, start, None)
+		.await?;
+	let next = next_start(&keys, count);
+	let mut rows = Vec::with_capacity(keys.len());
+	for key in keys {
+		let (account, currency_id) = double_map_key::<FreeBalances, _, _>(&key)?;
+		rows.push(HolderRow {
+			account,
+			currency_id,
+			free: value::<FreeBalances>(client, key).await?,
+		});
+	}
+	Ok(Page { rows, next })
+}
+
+/// Accounts with an autonomy stake.
+pub async fn stakers_page(
+	client: &Client<XPredictRuntime>,
+	count: u32,
+	start: Option<StorageKey>,
+) -> Result<Page<AccountRow>> {
+	let keys = client.fetch_keys::<Stakes>(count, start, None).await?;
+	let next = next_start(&keys, count);
+	let mut rows = Vec::with_capacity(keys.len());
+	for key in keys {
+		rows.push(AccountRow {
+			account: map_key::<Stakes, _>(&key)?,
+			staked: value::<Stakes>(client, key).await?,
+		});
+	}
+	Ok(Page { rows, next })
+}
+
+/// Tagged accounts allowed to upload results.
+pub async fn uploaders_page(
+	client: &Client<XPredictRuntime>,
+	count: u32,
+	start: Option<StorageKey>,
+) -> Result<Page<AccountRow>> {
+	let keys = client.fetch_keys::<Uploaders>(count, start, None).await?;
+	let next = next_start(&keys, count);
+	let mut rows = Vec::with_capacity(keys.len());
+	for key in keys {
+		let account = map_key::<Uploaders, _>(&key)?;
+		let staked = XPredictLogic::autonomy_stake_account(client, &account).await?;
+		rows.push(AccountRow { account, staked });
+	}
+	Ok(Page { rows, next })
+}
+
+/// Follows `page` from the first key to the last page.
+pub async fn all_pages<T, F, Fut>(mut page: F) -> Result<Vec<T>>
+where
+	F: FnMut(Option<StorageKey>) -> Fut,
+	Fut: Future<Output = Result<Page<T>>>,
+{
+	let mut rows = Vec::new();
+	let mut start = None;
+	loop {
+		let Page {
+			rows: page_rows,
+			next,
+		} = page(start).await?;
+		rows.extend(page_rows);
+		match next {
+			Some(next) => start = Some(next),
+			None => return Ok(rows),
+		}
+	}
+}
+
+/// Proposals open for trading.
+pub async fn open_markets(
+	client: &Client<XPredictRuntime>,
+	page_size: u32,
+) -> Result<Vec<ProposalRow>> {
+	let proposals = all_pages(|start| proposals_page(client, page_size, start)).await?;
+	Ok(proposals
+		.into_iter()
+		.filter(|row| row.status == ProposalStatus::FormalPrediction)
+		.collect())
+}
+
+/// Every account that can upload results.
+pub async fn result_uploaders(
+	client: &Client<XPredictRuntime>,
+	page_size: u32,
+) -> Result<Vec<AccountRow>> {
+	all_pages(|start| uploaders_page(client, page_size, start)).await
+}
+
+fn next_start(keys: &[StorageKey], count: u32) -> Option<StorageKey> {
+	if keys.len() < count as usize {
+		return None;
+	}
+	keys.last().cloned()
+}
+
+/// The value under a key listed from the `S` map.
+async fn value<S: Store<XPredictRuntime>>(
+	client: &Client<XPredictRuntime>,
+	key: StorageKey,
+) -> Result<S::Returns> {
+	let hex = format!("0x{}", hex::encode(&key.0));
+	client
+		.fetch_unhashed::<S::Returns>(key, None)
+		.await?
+		.ok_or_else(|| XPredictError::not_found::<S>(hex))
+}
+
+/// Decodes the key of a map entry, whichever of the `Blake2_128Concat`,
+/// `Twox64Concat` or `Identity` hashers the map uses. Other hashers cannot
+/// be reversed.
+fn map_key<S: Store<XPredictRuntime>, K: Decode>(key: &StorageKey) -> Result<K> {
+	let mut input = key.0.get(PREFIX_LENGTH..).unwrap_or_default();
+	take_key(&mut input)
+		.filter(|_| input.is_empty())
+		.ok_or_else(|| key_error::<S>(key))
+}
+
+/// Decodes the two keys of a double map entry, see `map_key`.
+fn double_map_key<S: Store<XPredictRuntime>, K1: Decode, K2: Decode>(
+	key: &StorageKey,
+) -> Result<(K1, K2)> {
+	let mut input = key.0.get(PREFIX_LENGTH..).unwrap_or_default();
+	let first = take_key(&mut input).ok_or_else(|| key_error::<S>(key))?;
+	let second = take_key(&mut input)
+		.filter(|_| input.is_empty())
+		.ok_or_else(|| key_error::<S>(key))?;
+	Ok((first, second))
+}
+
+fn key_error<S: Store<XPredictRuntime>>(key: &StorageKey) -> XPredictError {
+	XPredictError::StorageKey {
+		module: S::MODULE,
+		field: S::FIELD,
+		key: format!("0x{}", hex::encode(&key.0)),
+	}
+}
+
+fn take_key<K: Decode>(input: &mut &[u8]) -> Option<K> {
+	let hashers: [(usize, fn(&[u8]) -> Vec<u8>); 3] = [
+		(16, |data| blake2_128(data).to_vec()),
+		(8, |data| twox_64(data).to_vec()),
+		(0, |_| Vec::new()),
+	];
+	for (length, hash) in hashers.iter() {
+		if input.len() < *length {
+			continue;
+		}
+		let (prefix, encoded) = input.split_at(*length);
+		let mut rest = encoded;
+		if let Ok(key) = K::decode(&mut rest) {
+			if hash(&encoded[..encoded.len() - rest.len()]) == prefix {
+				*input = rest;
+				return Some(key);
+			}
+		}
+	}
+	None
+}
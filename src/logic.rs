@@ -1,4 +1,5 @@
 use std::{
+	collections::BTreeMap,
 	convert::TryInto,
 	marker::PhantomData,
 	time::{Duration, SystemTime},
@@ -6,90 +7,201 @@ use std::{
 
 use chrono::{DateTime, NaiveDateTime, Utc};
 use codec::Encode;
+use futures::{pin_mut, StreamExt};
 use sp_core::Pair;
 use sp_runtime::{traits::Verify, MultiSignature};
 use subxt::{
-	extrinsic::create_unsigned, sp_core::sr25519::Pair as Sr25519Pair, sudo::*, system::*, Client,
-	PairSigner,
+	extrinsic::create_unsigned, sudo::*, system::*, Client, Encoded, Event, ExtrinsicSuccess,
 };
 
 use crate::{
-	pallets::{autonomy::*, couple::*, proposals::*, tokens::*, *},
+	amm::{PoolState, TradeLimit},
+	dispatch::decode_dispatch_error,
+	error::{Result, XPredictError},
+	events::status_transitions,
+	pairs::XPredictSigner,
+	pallets::{autonomy::*, couple::*, proposals::*, ruler::*, tokens::*, utility::*, *},
 	runtime::XPredictRuntime,
 };
 
+pub fn expect_event<E: Event<XPredictRuntime>>(
+	result: &ExtrinsicSuccess<XPredictRuntime>,
+) -> Result<E> {
+	result
+		.find_event::<E>()?
+		.ok_or_else(|| XPredictError::event_not_found::<E>(result.block))
+}
+
+/// Submits `call` through `Sudo::sudo` and fails if the inner dispatch did,
+/// which subxt alone reports as a successful extrinsic.
+pub async fn sudo(
+	client: &Client<XPredictRuntime>,
+	signer: &XPredictSigner,
+	call: &Encoded,
+) -> Result<ExtrinsicSuccess<XPredictRuntime>> {
+	let result = client.sudo_and_watch(signer, call).await?;
+	let sudid = expect_event::<SudidEvent<_>>(&result)?;
+	if let Err(error) = sudid.dispatch_result {
+		return Err(XPredictError::Extrinsic(decode_dispatch_error(
+			client.metadata(),
+			error,
+		)));
+	}
+	Ok(result)
+}
+
+/// Submits `calls` through `Utility::batch`, failing with the index of the
+/// first call that did.
+pub async fn batch(
+	client: &Client<XPredictRuntime>,
+	signer: &XPredictSigner,
+	calls: Vec<Encoded>,
+) -> Result<ExtrinsicSuccess<XPredictRuntime>> {
+	let call = BatchCall {
+		calls: calls.into_iter().map(|call| RawCall(call.0)).collect(),
+		_runtime: PhantomData,
+	};
+	let result = client.watch(call, signer).await?;
+	if let Some(interrupted) = result.find_event::<BatchInterruptedEvent<_>>()? {
+		return Err(XPredictError::BatchInterrupted {
+			index: interrupted.index,
+			error: decode_dispatch_error(client.metadata(), interrupted.error),
+		});
+	}
+	Ok(result)
+}
+
 pub struct XPredictLogic;
 
 impl XPredictLogic {
 	pub async fn new_asset(
 		client: &Client<XPredictRuntime>,
-		signer: &PairSigner<XPredictRuntime, Sr25519Pair>,
+		signer: &XPredictSigner,
 		decimals: u8,
-	) -> Result<CurrencyId, Box<dyn std::error::Error>> {
-		let call = client
-			.encode(NewAssetCall {
-				name: "foo".as_bytes().to_vec(),
-				symbol: "bar".as_bytes().to_vec(),
-				decimals,
-				_runtime: PhantomData,
-			})
-			.unwrap();
-		let result = client.sudo_and_watch(signer, &call).await?;
-
-		let currency_id = result.new_asset()?.unwrap().currency_id;
-		Ok(currency_id)
+	) -> Result<CurrencyId> {
+		let call = client.encode(NewAssetCall {
+			name: "foo".as_bytes().to_vec(),
+			symbol: "bar".as_bytes().to_vec(),
+			decimals,
+			_runtime: PhantomData,
+		})?;
+		let result = sudo(client, signer, &call).await?;
+		let event = expect_event::<NewAssetEvent<_>>(&result)?;
+		Ok(event.currency_id)
 	}
 
 	pub async fn mint_token(
 		client: &Client<XPredictRuntime>,
-		signer: &PairSigner<XPredictRuntime, Sr25519Pair>,
+		signer: &XPredictSigner,
 		currency_id: CurrencyId,
 		to: &<XPredictRuntime as System>::AccountId,
 		number: Balance,
-	) -> Result<(), Box<dyn std::error::Error>> {
-		let call = client
-			.encode(MintCall {
-				currency_id,
-				to,
-				number,
-			})
-			.unwrap();
-		let result = client.sudo_and_watch(signer, &call).await?;
-		if result.mint()?.is_some() {
-			Ok(())
-		} else {
-			Err("unknown error".into())
-		}
+	) -> Result<()> {
+		let call = client.encode(MintCall {
+			currency_id,
+			to,
+			number,
+		})?;
+		let result = sudo(client, signer, &call).await?;
+		expect_event::<MintEvent<_>>(&result)?;
+		Ok(())
 	}
 
 	pub async fn transfer_token(
 		client: &Client<XPredictRuntime>,
-		signer: &PairSigner<XPredictRuntime, Sr25519Pair>,
+		signer: &XPredictSigner,
 		currency_id: CurrencyId,
 		to: &<XPredictRuntime as System>::AccountId,
 		number: Balance,
-	) -> Result<(), Box<dyn std::error::Error>> {
+	) -> Result<()> {
 		let result = client
 			.transfer_and_watch(signer, currency_id, to, number)
 			.await?;
-		if result.transfer()?.is_some() {
-			Ok(())
-		} else {
-			Err("unknown error".into())
-		}
+		expect_event::<TransferEvent<_>>(&result)?;
+		Ok(())
+	}
+
+	pub async fn burn_token(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		currency_id: CurrencyId,
+		number: Balance,
+	) -> Result<Balance> {
+		let result = client.burn_and_watch(signer, currency_id, number).await?;
+		let event = expect_event::<BurnEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	/// Burns tokens of `from` out of the allowance it gave the signer.
+	pub async fn burn_token_from(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		currency_id: CurrencyId,
+		from: &<XPredictRuntime as System>::AccountId,
+		number: Balance,
+	) -> Result<Balance> {
+		let result = client
+			.burn_from_and_watch(signer, currency_id, from, number)
+			.await?;
+		let event = expect_event::<BurnFromEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	/// Sets the allowance of `spender` and returns the approved number.
+	pub async fn approve_token(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		currency_id: CurrencyId,
+		spender: &<XPredictRuntime as System>::AccountId,
+		number: Balance,
+	) -> Result<Balance> {
+		let result = client
+			.approve_and_watch(signer, currency_id, spender, number)
+			.await?;
+		let event = expect_event::<ApprovalEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	/// Increases the allowance of `spender` and returns the approved number.
+	pub async fn add_approve_token(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		currency_id: CurrencyId,
+		spender: &<XPredictRuntime as System>::AccountId,
+		number: Balance,
+	) -> Result<Balance> {
+		let result = client
+			.add_approve_and_watch(signer, currency_id, spender, number)
+			.await?;
+		let event = expect_event::<ApprovalEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	/// Transfers tokens of `from` out of the allowance it gave the signer.
+	pub async fn transfer_token_from(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		currency_id: CurrencyId,
+		from: &<XPredictRuntime as System>::AccountId,
+		to: &<XPredictRuntime as System>::AccountId,
+		number: Balance,
+	) -> Result<Balance> {
+		let result = client
+			.transfer_from_and_watch(signer, currency_id, from, to, number)
+			.await?;
+		let event = expect_event::<TransferFromEvent<_>>(&result)?;
+		Ok(event.number)
 	}
 
 	pub async fn make_proposal(
 		client: &Client<XPredictRuntime>,
-		signer: &PairSigner<XPredictRuntime, Sr25519Pair>,
+		signer: &XPredictSigner,
 		currency_id: CurrencyId,
 		number: Balance,
-	) -> Result<ProposalId, Box<dyn std::error::Error>> {
-		let close_time = SystemTime::now()
-			.duration_since(SystemTime::UNIX_EPOCH)?
-			.checked_add(Duration::from_secs(11 * 60)) // 11 min
-			.ok_or("unknown error")?
-			.as_millis() as Moment;
+		close_after: Duration,
+		earn_fee: u32,
+	) -> Result<ProposalId> {
+		let close_time = Self::close_time_after(close_after)?;
 		let result = client
 			.new_proposal_and_watch(
 				signer,
@@ -99,101 +211,285 @@ impl XPredictLogic {
 				1,
 				currency_id,
 				number,
-				2000,
+				earn_fee,
 				"".as_bytes().to_vec(),
 			)
 			.await?;
-		let proposal_id = result.new_proposal()?.ok_or("unknown error")?.proposal_id;
-		Ok(proposal_id)
+		let event = expect_event::<NewProposalEvent<_>>(&result)?;
+		Ok(event.proposal_id)
 	}
 
 	pub async fn quick_to_formal(
 		client: &Client<XPredictRuntime>,
-		signer: &PairSigner<XPredictRuntime, Sr25519Pair>,
+		signer: &XPredictSigner,
 		proposal_id: ProposalId,
-	) -> Result<(), Box<dyn std::error::Error>> {
-		let call = client
-			.encode(SetStatusCall {
-				proposal_id,
-				new_status: ProposalStatus::FormalPrediction,
-				_runtime: PhantomData,
-			})
-			.unwrap();
-		let result = client.sudo_and_watch(signer, &call).await?;
-		if result.proposal_status_changed()?.is_some() {
-			Ok(())
-		} else {
-			Err("unknown error".into())
-		}
+	) -> Result<()> {
+		Self::proposal_set_status(
+			client,
+			signer,
+			proposal_id,
+			ProposalStatus::FormalPrediction,
+		)
+		.await
+	}
+
+	pub async fn proposal_set_status(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		status: ProposalStatus,
+	) -> Result<()> {
+		let call = client.encode(SetStatusCall {
+			proposal_id,
+			new_status: status,
+			_runtime: PhantomData,
+		})?;
+		let result = sudo(client, signer, &call).await?;
+		expect_event::<ProposalStatusChangedEvent<_>>(&result)?;
+		Ok(())
 	}
 
 	pub async fn proposal_buy(
 		client: &Client<XPredictRuntime>,
-		signer: &PairSigner<XPredictRuntime, Sr25519Pair>,
+		signer: &XPredictSigner,
 		proposal_id: ProposalId,
 		currency_id: CurrencyId,
 		number: Balance,
-	) -> Result<Balance, Box<dyn std::error::Error>> {
+	) -> Result<Balance> {
 		let result = client
 			.buy_and_watch(signer, proposal_id, currency_id, number)
 			.await?;
-		if let Some(event) = result.buy()? {
-			Ok(event.number)
-		} else {
-			Err("unknown error".into())
+		let event = expect_event::<BuyEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	pub async fn proposal_sell(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+		number: Balance,
+	) -> Result<Balance> {
+		let result = client
+			.sell_and_watch(signer, proposal_id, currency_id, number)
+			.await?;
+		let event = expect_event::<SellEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	/// `proposal_buy` that re-quotes the pool first and refuses to submit if
+	/// `limit` would be broken. Fails with `BelowMinimum` if the bought number
+	/// still falls short, after the trade has gone through.
+	pub async fn buy_with_limit(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+		number: Balance,
+		limit: TradeLimit,
+	) -> Result<Balance> {
+		let pool = PoolState::fetch(client, proposal_id).await?;
+		limit.check(proposal_id, &pool.quote_buy(currency_id, number)?, true)?;
+		let received = Self::proposal_buy(client, signer, proposal_id, currency_id, number).await?;
+		limit.check_output(proposal_id, received)?;
+		Ok(received)
+	}
+
+	/// Selling counterpart of `buy_with_limit`.
+	pub async fn sell_with_limit(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+		number: Balance,
+		limit: TradeLimit,
+	) -> Result<Balance> {
+		let pool = PoolState::fetch(client, proposal_id).await?;
+		limit.check(proposal_id, &pool.quote_sell(currency_id, number)?, false)?;
+		let received =
+			Self::proposal_sell(client, signer, proposal_id, currency_id, number).await?;
+		limit.check_output(proposal_id, received)?;
+		Ok(received)
+	}
+
+	pub async fn proposal_retrieval(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+		number: Balance,
+	) -> Result<Balance> {
+		let result = client
+			.retrieval_and_watch(signer, proposal_id, currency_id, number)
+			.await?;
+		let event = expect_event::<RetrievalEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	pub async fn proposal_add_liquidity(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		number: Balance,
+	) -> Result<Balance> {
+		let result = client
+			.add_liquidity_and_watch(signer, proposal_id, number)
+			.await?;
+		let event = expect_event::<AddLiquidityEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	pub async fn proposal_remove_liquidity(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		number: Balance,
+	) -> Result<Balance> {
+		let result = client
+			.remove_liquidity_and_watch(signer, proposal_id, number)
+			.await?;
+		let event = expect_event::<RemoveLiquiditEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	/// Withdraws the liquidity provider reward of the couple pool, not the
+	/// proposals pallet reward of the same name.
+	pub async fn proposal_withdrawal_reward(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+	) -> Result<Balance> {
+		let call = couple::WithdrawalRewardCall {
+			proposal_id,
+			_runtime: PhantomData,
+		};
+		let result = client.watch(call, signer).await?;
+		let event = expect_event::<couple::WithdrawalRewardEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	pub async fn proposal_set_result(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+	) -> Result<CurrencyId> {
+		let call = client.encode(SetResultCall {
+			proposal_id,
+			currency_id,
+			_runtime: PhantomData,
+		})?;
+		let result = sudo(client, signer, &call).await?;
+		let event = expect_event::<SetResultEvent<_>>(&result)?;
+		Ok(event.currency_id)
+	}
+
+	/// Stakes `number` for (`opinion`) or against an `OriginalPrediction`
+	/// proposal, returning the staked number.
+	pub async fn proposal_stake_to(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		number: Balance,
+		opinion: bool,
+	) -> Result<Balance> {
+		let status = Self::proposal_status(client, proposal_id).await?;
+		if status != ProposalStatus::OriginalPrediction {
+			return Err(XPredictError::UnexpectedStatus {
+				proposal_id,
+				expected: ProposalStatus::OriginalPrediction,
+				found: status,
+			});
 		}
+		let result = client
+			.stake_to_and_watch(signer, proposal_id, number, opinion)
+			.await?;
+		let event = expect_event::<StakeToEvent<_>>(&result)?;
+		Ok(event.number)
 	}
 
-	// pub async fn proposal_retrieval(
-	// 	client: &Client<XPredictRuntime>,
-	// 	signer: &PairSigner<XPredictRuntime, Sr25519Pair>,
-	// 	proposal_id: ProposalId,
-	// 	currency_id: CurrencyId,
-	// 	number: Balance,
-	// ) -> Result<Balance, Box<dyn std::error::Error>> {
-	// 	let result = client
-	// 		.retrieval_and_watch(signer, proposal_id, currency_id, number)
-	// 		.await?;
-	// 	if let Some(event) = result.retrieval()? {
-	// 		Ok(event.number)
-	// 	} else {
-	// 		Err("unknown error".into())
-	// 	}
-	// }
+	pub async fn proposal_unstake_from(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+	) -> Result<Balance> {
+		let result = client.unstake_from_and_watch(signer, proposal_id).await?;
+		let event = expect_event::<UnStakeFromEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	/// The stake and opinion of `account` on a proposal, if it voted.
+	pub async fn proposal_vote_stake(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+		account: &<XPredictRuntime as System>::AccountId,
+	) -> Result<Option<(Balance, bool)>> {
+		Ok(client
+			.proposal_vote_stake(proposal_id, account, None)
+			.await?)
+	}
+
+	/// Total stakes for and against a proposal.
+	pub async fn proposal_count_vote(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+	) -> Result<(Balance, Balance)> {
+		let yes = client.proposal_count_vote(proposal_id, true, None).await?;
+		let no = client.proposal_count_vote(proposal_id, false, None).await?;
+		Ok((yes.unwrap_or(0), no.unwrap_or(0)))
+	}
+
+	pub async fn proposal_minimum_vote(client: &Client<XPredictRuntime>) -> Result<Balance> {
+		client
+			.minimum_vote(None)
+			.await?
+			.ok_or_else(|| XPredictError::not_found::<MinimumVoteStore<XPredictRuntime>>(()))
+	}
+
+	/// Whether the stakes on both sides together reached `MinimumVote`.
+	pub async fn proposal_vote_reached(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+	) -> Result<bool> {
+		let (yes, no) = Self::proposal_count_vote(client, proposal_id).await?;
+		let minimum = Self::proposal_minimum_vote(client).await?;
+		Ok(yes.saturating_add(no) >= minimum)
+	}
 
 	pub async fn autonomy_stake(
 		client: &Client<XPredictRuntime>,
-		signer: &PairSigner<XPredictRuntime, Sr25519Pair>,
-	) -> Result<(), Box<dyn std::error::Error>> {
+		signer: &XPredictSigner,
+	) -> Result<()> {
 		let result = client.stake_and_watch(signer).await?;
-		if result.stake()?.is_some() {
-			Ok(())
-		} else {
-			Err("unknown error".into())
-		}
+		expect_event::<StakeEvent<_>>(&result)?;
+		Ok(())
 	}
 
 	pub async fn autonomy_tag(
 		client: &Client<XPredictRuntime>,
-		signer: &PairSigner<XPredictRuntime, Sr25519Pair>,
+		signer: &XPredictSigner,
 		target: &<XPredictRuntime as System>::AccountId,
-	) -> Result<(), Box<dyn std::error::Error>> {
-		let call = client.encode(TaggingCall { target }).unwrap();
-		let result = client.sudo_and_watch(signer, &call).await?;
-		if result.tagging()?.is_some() {
-			Ok(())
-		} else {
-			Err("unknown error".into())
-		}
+	) -> Result<()> {
+		let call = client.encode(TaggingCall { target })?;
+		let result = sudo(client, signer, &call).await?;
+		expect_event::<TaggingEvent<_>>(&result)?;
+		Ok(())
 	}
 
-	pub async fn autonomy_upload(
+	/// Uploads a result as an unsigned extrinsic, signing the payload with
+	/// `pair` of any scheme the runtime's `MultiSignature` accepts.
+	pub async fn autonomy_upload<P>(
 		client: &Client<XPredictRuntime>,
-		signer: &PairSigner<XPredictRuntime, Sr25519Pair>,
+		pair: &P,
 		proposal_id: ProposalId,
 		result: CurrencyId,
-	) -> Result<(), Box<dyn std::error::Error>> {
-		let public: <MultiSignature as Verify>::Signer = signer.signer().public().into();
+	) -> Result<()>
+	where
+		P: Pair,
+		MultiSignature: From<P::Signature>,
+		<MultiSignature as Verify>::Signer: From<P::Public>,
+	{
+		let public: <MultiSignature as Verify>::Signer = pair.public().into();
 		let payload = Payload {
 			proposal_id,
 			result,
@@ -201,107 +497,434 @@ impl XPredictLogic {
 		};
 		let encoded_upload_call = client.encode(UploadResultCall {
 			payload: payload.clone(),
-			signature: signer.signer().sign(&(payload.encode())).into(),
+			signature: pair.sign(&(payload.encode())).into(),
 			_runtime: PhantomData,
 		})?;
 		let result = client
 			.submit_and_watch_extrinsic(create_unsigned::<XPredictRuntime>(encoded_upload_call))
 			.await?;
-		if result.upload_result()?.is_some() {
-			Ok(())
-		} else {
-			Err("unknown error".into())
-		}
+		expect_event::<UploadResultEvent<_>>(&result)?;
+		Ok(())
+	}
+
+	pub async fn autonomy_report(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		target: &<XPredictRuntime as System>::AccountId,
+		number: Balance,
+	) -> Result<Balance> {
+		let result = client
+			.report_and_watch(signer, proposal_id, target, number)
+			.await?;
+		let event = expect_event::<ReportEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	/// Seconds (`support`) or opposes an existing report on `target` and
+	/// returns the side recorded by the chain.
+	pub async fn autonomy_seconded_report(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		target: &<XPredictRuntime as System>::AccountId,
+		number: Balance,
+		support: bool,
+	) -> Result<bool> {
+		let result = client
+			.seconded_report_and_watch(signer, proposal_id, target, number, support)
+			.await?;
+		let event = expect_event::<SecondedReportEvent<_>>(&result)?;
+		Ok(event.approve)
+	}
+
+	pub async fn autonomy_take_out(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		proposal_id: ProposalId,
+		target: &<XPredictRuntime as System>::AccountId,
+	) -> Result<Balance> {
+		let result = client
+			.take_out_and_watch(signer, proposal_id, target)
+			.await?;
+		let event = expect_event::<TakeOutEvent<_>>(&result)?;
+		Ok(event.number)
+	}
+
+	/// The result `account` uploaded for a proposal, if any.
+	pub async fn autonomy_temporary_result(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+		account: &<XPredictRuntime as System>::AccountId,
+	) -> Result<Option<CurrencyId>> {
+		Ok(client.temporary_results(proposal_id, account, None).await?)
+	}
+
+	/// Stake `account` put on its reports against a proposal's uploaders.
+	pub async fn autonomy_report_staked(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+		account: &<XPredictRuntime as System>::AccountId,
+	) -> Result<Balance> {
+		let result = client
+			.report_staked_number(proposal_id, account, None)
+			.await?;
+		Ok(result.unwrap_or(0))
+	}
+
+	/// Reporters of `target` on a proposal, with their side and stake.
+	pub async fn autonomy_report_account(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+		target: &<XPredictRuntime as System>::AccountId,
+	) -> Result<BTreeMap<<XPredictRuntime as System>::AccountId, (bool, Balance)>> {
+		let result = client.report_account(proposal_id, target, None).await?;
+		Ok(result.unwrap_or_default())
+	}
+
+	/// Stakes for and against the reports of a proposal.
+	pub async fn autonomy_statistical_report(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+	) -> Result<(Balance, Balance)> {
+		let approve = client.statistical_report(proposal_id, true, None).await?;
+		let oppose = client.statistical_report(proposal_id, false, None).await?;
+		Ok((approve.unwrap_or(0), oppose.unwrap_or(0)))
+	}
+
+	pub async fn autonomy_report_time(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+	) -> Result<Moment> {
+		client
+			.proposal_report_time(proposal_id, None)
+			.await?
+			.ok_or_else(|| {
+				XPredictError::not_found::<ProposalReportTimeStore<XPredictRuntime>>(proposal_id)
+			})
+	}
+
+	pub async fn autonomy_publicity_interval(client: &Client<XPredictRuntime>) -> Result<Moment> {
+		client
+			.publicity_interval(None)
+			.await?
+			.ok_or_else(|| XPredictError::not_found::<PublicityIntervalStore<XPredictRuntime>>(()))
+	}
+
+	/// Stake uploaded for `currency_id` as the result of a proposal.
+	pub async fn autonomy_statistical_result(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+		currency_id: CurrencyId,
+	) -> Result<Balance> {
+		let result = client
+			.statistical_results(proposal_id, currency_id, None)
+			.await?;
+		Ok(result.unwrap_or(0))
+	}
+
+	pub async fn autonomy_report_interval(client: &Client<XPredictRuntime>) -> Result<Moment> {
+		client
+			.report_interval(None)
+			.await?
+			.ok_or_else(|| XPredictError::not_found::<ReportIntervalStore<XPredictRuntime>>(()))
+	}
+
+	pub async fn autonomy_minimal_report_number(
+		client: &Client<XPredictRuntime>,
+	) -> Result<Balance> {
+		client.minimal_report_number(None).await?.ok_or_else(|| {
+			XPredictError::not_found::<MinimalReportNumberStore<XPredictRuntime>>(())
+		})
 	}
 
 	pub async fn autonomy_minimal_stake_number(
 		client: &Client<XPredictRuntime>,
-	) -> Result<Balance, Box<dyn std::error::Error>> {
-		let result = client.minimal_stake_number(None).await?;
-		if let Some(number) = result {
-			Ok(number)
-		} else {
-			Err("unknown error".into())
-		}
+	) -> Result<Balance> {
+		client
+			.minimal_stake_number(None)
+			.await?
+			.ok_or_else(|| XPredictError::not_found::<MinimalStakeNumberStore<XPredictRuntime>>(()))
+	}
+
+	/// Currency `MinimalStakeNumber` is counted in, the `StakeCurrencyId`
+	/// constant of the autonomy pallet.
+	pub fn autonomy_stake_currency(client: &Client<XPredictRuntime>) -> Result<CurrencyId> {
+		let currency_id = client
+			.metadata()
+			.module("Autonomy")
+			.and_then(|module| module.constant("StakeCurrencyId"))
+			.and_then(|constant| constant.value())
+			.map_err(subxt::Error::from)?;
+		Ok(currency_id)
 	}
 
 	pub async fn autonomy_stake_account(
 		client: &Client<XPredictRuntime>,
 		target: &<XPredictRuntime as System>::AccountId,
-	) -> Result<Balance, Box<dyn std::error::Error>> {
+	) -> Result<Balance> {
 		let result = client.staked_account(target, None).await?;
-		if let Some(number) = result {
-			Ok(number)
-		} else {
-			Ok(0)
-		}
+		Ok(result.unwrap_or(0))
 	}
 
 	pub async fn autonomy_account(
 		client: &Client<XPredictRuntime>,
 		target: &<XPredictRuntime as System>::AccountId,
-	) -> Result<(), Box<dyn std::error::Error>> {
+	) -> Result<()> {
+		client.autonomy_account(target, None).await?.ok_or_else(|| {
+			XPredictError::not_found::<AutonomyAccountStore<'_, XPredictRuntime>>(target)
+		})
+	}
+
+	/// Title, category and detail of a proposal.
+	pub async fn proposal_info(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+	) -> Result<Proposal> {
+		// couple's `StakedAccountStore` holds the proposals, the name clashes
+		// with autonomy's so the generated accessor is ambiguous
+		let store = couple::StakedAccountStore::<XPredictRuntime> {
+			proposal_id,
+			_runtime: PhantomData,
+		};
+		client.fetch(&store, None).await?.ok_or_else(|| {
+			XPredictError::not_found::<couple::StakedAccountStore<XPredictRuntime>>(proposal_id)
+		})
+	}
+
+	/// Currency a proposal's pool trades against.
+	pub async fn proposal_currency(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+	) -> Result<CurrencyId> {
 		client
-			.autonomy_account(target, None)
+			.proposal_currency_id(proposal_id, None)
+			.await?
+			.ok_or_else(|| {
+				XPredictError::not_found::<ProposalCurrencyIdStore<XPredictRuntime>>(proposal_id)
+			})
+	}
+
+	pub async fn proposal_total_volume(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+	) -> Result<Balance> {
+		let result = client.proposal_total_volume(proposal_id, None).await?;
+		Ok(result.unwrap_or(0))
+	}
+
+	pub async fn proposal_owner(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+	) -> Result<Option<<XPredictRuntime as System>::AccountId>> {
+		Ok(client.proposal_owner(proposal_id, None).await?)
+	}
+
+	/// The winning token once the result is set.
+	pub async fn proposal_result(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+	) -> Result<Option<CurrencyId>> {
+		Ok(client.proposal_result(proposal_id, None).await?)
+	}
+
+	/// Trading fee the owner of a proposal has withdrawn so far.
+	pub async fn proposal_owner_withdrawn_fee(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+		account: &<XPredictRuntime as System>::AccountId,
+	) -> Result<Balance> {
+		let result = client
+			.proposal_owner_already_withdrawn_fee(proposal_id, account, None)
+			.await?;
+		Ok(result.unwrap_or(0))
+	}
+
+	/// Autonomy reward of a proposal accrued after `account` joined its pool,
+	/// from `ProposalCurrentAutonomyRewardStore` and the account's start.
+	pub async fn proposal_pending_autonomy_reward(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+		account: &<XPredictRuntime as System>::AccountId,
+	) -> Result<Balance> {
+		let start = client
+			.proposal_account_reward_start(proposal_id, account, None)
+			.await?;
+		let start = match start {
+			Some(start) => start,
+			None => return Ok(0),
+		};
+		let current = client
+			.proposal_current_autonomy_reward(proposal_id, None)
 			.await?
-			.ok_or_else(|| "unknown error".into())
+			.unwrap_or(0);
+		Ok(current.saturating_sub(start))
+	}
+
+	/// Liquidity shares `account` holds in a proposal's pool.
+	pub async fn proposal_liquidity_of(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+		account: &<XPredictRuntime as System>::AccountId,
+	) -> Result<Balance> {
+		let result = client
+			.proposal_account_info(proposal_id, account, None)
+			.await?;
+		Ok(result.unwrap_or(0))
+	}
+
+	/// Number of proposals created so far, ids run from 0 to it.
+	pub async fn proposal_count(client: &Client<XPredictRuntime>) -> Result<ProposalId> {
+		Ok(client.current_proposal_id(None).await?.unwrap_or(0))
 	}
 
 	pub async fn proposal_pairs(
 		client: &Client<XPredictRuntime>,
 		proposal_id: ProposalId,
-	) -> Result<(CurrencyId, CurrencyId), Box<dyn std::error::Error>> {
-		let result = client.pool_pairs(proposal_id, None).await?;
-		if let Some(pairs) = result {
-			Ok(pairs)
-		} else {
-			Err("proposal id incorrect".into())
-		}
+	) -> Result<(CurrencyId, CurrencyId)> {
+		client
+			.pool_pairs(proposal_id, None)
+			.await?
+			.ok_or_else(|| XPredictError::not_found::<PoolPairsStore<XPredictRuntime>>(proposal_id))
 	}
 
 	pub async fn proposal_optional(
 		client: &Client<XPredictRuntime>,
 		proposal_id: ProposalId,
-	) -> Result<(Balance, Balance), Box<dyn std::error::Error>> {
-		let result = client
+	) -> Result<(Balance, Balance)> {
+		client
 			.proposal_total_optional_market(proposal_id, None)
-			.await?;
-		if let Some(pairs) = result {
-			Ok(pairs)
-		} else {
-			Err("proposal id incorrect".into())
-		}
+			.await?
+			.ok_or_else(|| {
+				XPredictError::not_found::<ProposalTotalOptionalMarketStore<XPredictRuntime>>(
+					proposal_id,
+				)
+			})
 	}
 
 	pub async fn proposal_status(
 		client: &Client<XPredictRuntime>,
 		proposal_id: ProposalId,
-	) -> Result<ProposalStatus, Box<dyn std::error::Error>> {
-		let result = client.proposal_status(proposal_id, None).await?;
-		if let Some(state) = result {
-			Ok(state)
-		} else {
-			Err("proposal id incorrect".into())
-		}
+	) -> Result<ProposalStatus> {
+		client
+			.proposal_status(proposal_id, None)
+			.await?
+			.ok_or_else(|| {
+				XPredictError::not_found::<ProposalStatusStore<XPredictRuntime>>(proposal_id)
+			})
+	}
+
+	/// Waits until the proposal has `target` status, following its
+	/// `ProposalStatusChangedEvent`s. The status is only read again when a
+	/// block that may have held one could not be read. Fails if the proposal
+	/// ends first or `timeout` elapses.
+	pub async fn wait_for_status(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+		target: ProposalStatus,
+		timeout: Duration,
+	) -> Result<()> {
+		let wait = async {
+			// subscribe before reading so no change falls in between
+			let transitions = status_transitions(client, Some(proposal_id)).await?;
+			pin_mut!(transitions);
+			let mut status = Self::proposal_status(client, proposal_id).await?;
+			loop {
+				if status == target {
+					return Ok(());
+				}
+				if status == ProposalStatus::End {
+					return Err(XPredictError::UnexpectedStatus {
+						proposal_id,
+						expected: target,
+						found: status,
+					});
+				}
+				status = match transitions
+					.next()
+					.await
+					.ok_or(XPredictError::SubscriptionClosed)?
+				{
+					Ok(transition) => transition.status,
+					Err(_) => Self::proposal_status(client, proposal_id).await?,
+				};
+			}
+		};
+		async_std::future::timeout(timeout, wait)
+			.await
+			.map_err(|_| XPredictError::StatusTimeout {
+				proposal_id,
+				target,
+				timeout,
+			})?
+	}
+
+	/// When the result of a proposal was announced, `None` before that.
+	pub async fn proposal_announcement_time(
+		client: &Client<XPredictRuntime>,
+		proposal_id: ProposalId,
+	) -> Result<Option<Moment>> {
+		Ok(client.proposal_announcement_time(proposal_id, None).await?)
 	}
 
 	pub async fn proposal_close_time(
 		client: &Client<XPredictRuntime>,
 		proposal_id: ProposalId,
-	) -> Result<Moment, Box<dyn std::error::Error>> {
-		let result = client.proposal_close_time(proposal_id, None).await?;
-		if let Some(time) = result {
-			Ok(time)
-		} else {
-			Err("proposal id incorrect".into())
-		}
+	) -> Result<Moment> {
+		client
+			.proposal_close_time(proposal_id, None)
+			.await?
+			.ok_or_else(|| {
+				XPredictError::not_found::<ProposalCloseTimeStore<XPredictRuntime>>(proposal_id)
+			})
+	}
+
+	/// Proposes `address` as the next ruler of `module`; it takes over once it
+	/// calls `ruler_accept`.
+	pub async fn ruler_transfer(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		module: RulerModule,
+		address: &<XPredictRuntime as System>::AccountId,
+	) -> Result<<XPredictRuntime as System>::AccountId> {
+		let result = client
+			.transfer_ruler_address_and_watch(signer, module, address)
+			.await?;
+		let event = expect_event::<PendingRulerAddressEvent<_>>(&result)?;
+		Ok(event.to)
+	}
+
+	pub async fn ruler_accept(
+		client: &Client<XPredictRuntime>,
+		signer: &XPredictSigner,
+		module: RulerModule,
+	) -> Result<<XPredictRuntime as System>::AccountId> {
+		let result = client
+			.accept_ruler_address_and_watch(signer, module)
+			.await?;
+		let event = expect_event::<AcceptRulerAddressEvent<_>>(&result)?;
+		Ok(event.account)
+	}
+
+	pub async fn ruler_address(
+		client: &Client<XPredictRuntime>,
+		module: RulerModule,
+	) -> Result<Option<<XPredictRuntime as System>::AccountId>> {
+		Ok(client.ruler_address(module, None).await?)
+	}
+
+	pub async fn ruler_pending_address(
+		client: &Client<XPredictRuntime>,
+		module: RulerModule,
+	) -> Result<Option<<XPredictRuntime as System>::AccountId>> {
+		Ok(client.pending_ruler_address(module, None).await?)
 	}
 
 	pub async fn balance_of(
 		client: &Client<XPredictRuntime>,
 		currency_id: CurrencyId,
 		account: &<XPredictRuntime as System>::AccountId,
-	) -> Result<Balance, Box<dyn std::error::Error>> {
+	) -> Result<Balance> {
 		let balance = if currency_id == 0 {
 			let info = client.account(account, None).await?;
 			info.data.free
@@ -314,33 +937,109 @@ impl XPredictLogic {
 		Ok(balance)
 	}
 
-	pub async fn currencies(
+	pub async fn reserve_of(
 		client: &Client<XPredictRuntime>,
 		currency_id: CurrencyId,
-	) -> Result<PRC20, Box<dyn std::error::Error>> {
-		let result = client.currencies(currency_id, None).await?;
-		let token_info =
-			result.ok_or_else(|| Into::<Box<dyn std::error::Error>>::into("no such currency"))?;
-		Ok(token_info)
+		account: &<XPredictRuntime as System>::AccountId,
+	) -> Result<Balance> {
+		let reserved = if currency_id == 0 {
+			let info = client.account(account, None).await?;
+			info.data.reserved
+		} else {
+			client
+				.reserve_of(account, currency_id, None)
+				.await?
+				.unwrap_or(0)
+		};
+		Ok(reserved)
 	}
 
-	pub async fn number_of_currency(
+	/// Every spender `owner` approved, with the remaining allowance.
+	pub async fn allowance(
 		client: &Client<XPredictRuntime>,
-	) -> Result<CurrencyId, Box<dyn std::error::Error>> {
+		currency_id: CurrencyId,
+		owner: &<XPredictRuntime as System>::AccountId,
+	) -> Result<BTreeMap<<XPredictRuntime as System>::AccountId, Balance>> {
+		let result = client.allowance(owner, currency_id, None).await?;
+		Ok(result.unwrap_or_default())
+	}
+
+	pub async fn total_supply(
+		client: &Client<XPredictRuntime>,
+		currency_id: CurrencyId,
+	) -> Result<Balance> {
+		let result = client.total_supply(currency_id, None).await?;
+		Ok(result.unwrap_or(0))
+	}
+
+	pub async fn currencies(
+		client: &Client<XPredictRuntime>,
+		currency_id: CurrencyId,
+	) -> Result<PRC20> {
+		client.currencies(currency_id, None).await?.ok_or_else(|| {
+			XPredictError::not_found::<CurrenciesStore<XPredictRuntime>>(currency_id)
+		})
+	}
+
+	pub async fn number_of_currency(client: &Client<XPredictRuntime>) -> Result<CurrencyId> {
 		Ok(client.current_currency_id(None).await?.unwrap_or(0))
 	}
 
-	pub fn ts_format(ts: u64) -> Result<String, Box<dyn std::error::Error>> {
-		let naive = NaiveDateTime::from_timestamp(ts.try_into()?, 0);
+	/// The current time in milliseconds, the unit of the chain's moments.
+	pub fn now() -> Result<Moment> {
+		Self::close_time_after(Duration::from_secs(0))
+	}
+
+	pub fn close_time_after(duration: Duration) -> Result<Moment> {
+		let close_time = SystemTime::now()
+			.duration_since(SystemTime::UNIX_EPOCH)
+			.map_err(|e| XPredictError::Timestamp(e.to_string()))?
+			.checked_add(duration)
+			.ok_or_else(|| XPredictError::Timestamp("close time overflow".into()))?
+			.as_millis() as Moment;
+		Ok(close_time)
+	}
+
+	/// Formats `ts`, in seconds since the epoch, as a UTC date and time.
+	pub fn ts_format(ts: u64) -> Result<String> {
+		let secs = ts
+			.try_into()
+			.map_err(|_| XPredictError::Timestamp(format!("{} out of range", ts)))?;
+		let naive = NaiveDateTime::from_timestamp_opt(secs, 0)
+			.ok_or_else(|| XPredictError::Timestamp(format!("{} out of range", ts)))?;
 		let datetime: DateTime<Utc> = DateTime::from_utc(naive, Utc);
 		let newdate = datetime.format("%Y-%m-%d %H:%M:%S");
 		Ok(newdate.to_string())
 	}
 
-	pub fn now_format() -> Result<String, Box<dyn std::error::Error>> {
+	pub fn now_format() -> Result<String> {
 		let ts = SystemTime::now()
-			.duration_since(SystemTime::UNIX_EPOCH)?
-			.as_millis() as u64;
+			.duration_since(SystemTime::UNIX_EPOCH)
+			.map_err(|e| XPredictError::Timestamp(e.to_string()))?
+			.as_secs();
 		Self::ts_format(ts)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn formats_timestamps() {
+		assert_eq!(XPredictLogic::ts_format(0).unwrap(), "1970-01-01 00:00:00");
+		assert_eq!(
+			XPredictLogic::ts_format(1_600_000_000).unwrap(),
+			"2020-09-13 12:26:40"
+		);
+		assert!(matches!(
+			XPredictLogic::ts_format(u64::MAX),
+			Err(XPredictError::Timestamp(_))
+		));
+		assert!(matches!(
+			XPredictLogic::ts_format(i64::MAX as u64),
+			Err(XPredictError::Timestamp(_))
+		));
+		assert!(XPredictLogic::now_format().unwrap().starts_with("20"));
+	}
+}
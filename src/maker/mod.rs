@@ -0,0 +1,421 @@
+//! Liquidity bot for Couple pools: adds liquidity to matching proposals,
+//! trades them toward a target probability and pulls out before close.
+
+mod position;
+
+pub use position::{load_positions, save_positions, Inventory, Position, Positions};
+
+use std::collections::HashSet;
+
+use serde_json::{json, Value};
+use subxt::{system::System, Client};
+
+use crate::{
+	amm::{PoolState, TradeLimit},
+	config::MakerConfig,
+	error::Result,
+	logic::XPredictLogic,
+	pairs::XPredictSigner,
+	pallets::{Balance, CurrencyId, Moment, ProposalId, ProposalStatus},
+	runtime::XPredictRuntime,
+};
+
+type AccountId = <XPredictRuntime as System>::AccountId;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MakerAction {
+	AddLiquidity(Balance),
+	Buy {
+		currency_id: CurrencyId,
+		number: Balance,
+		received: Balance,
+	},
+	Sell {
+		currency_id: CurrencyId,
+		number: Balance,
+		received: Balance,
+	},
+	/// Shares burned and the tokens and fee reward they were quoted to return
+	RemoveLiquidity {
+		shares: Balance,
+		yes: Balance,
+		no: Balance,
+		fee_reward: Balance,
+	},
+	/// Retried on the next block
+	Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct MakerEvent {
+	pub proposal_id: ProposalId,
+	pub action: MakerAction,
+}
+
+impl MakerEvent {
+	pub fn to_json(&self) -> Value {
+		let proposal_id = self.proposal_id;
+		match &self.action {
+			MakerAction::AddLiquidity(number) => json!({
+				"proposal_id": proposal_id,
+				"action": "add_liquidity",
+				"number": number.to_string(),
+			}),
+			MakerAction::Buy {
+				currency_id,
+				number,
+				received,
+			} => json!({
+				"proposal_id": proposal_id,
+				"action": "buy",
+				"currency_id": currency_id,
+				"number": number.to_string(),
+				"received": received.to_string(),
+			}),
+			MakerAction::Sell {
+				currency_id,
+				number,
+				received,
+			} => json!({
+				"proposal_id": proposal_id,
+				"action": "sell",
+				"currency_id": currency_id,
+				"number": number.to_string(),
+				"received": received.to_string(),
+			}),
+			MakerAction::RemoveLiquidity {
+				shares,
+				yes,
+				no,
+				fee_reward,
+			} => json!({
+				"proposal_id": proposal_id,
+				"action": "remove_liquidity",
+				"shares": shares.to_string(),
+				"yes": yes.to_string(),
+				"no": no.to_string(),
+				"fee_reward": fee_reward.to_string(),
+			}),
+			MakerAction::Failed(error) => json!({
+				"proposal_id": proposal_id,
+				"action": "failed",
+				"error": error,
+			}),
+		}
+	}
+}
+
+pub struct Maker<'a> {
+	client: &'a Client<XPredictRuntime>,
+	signer: &'a XPredictSigner,
+	account: AccountId,
+	policy: MakerConfig,
+	positions: Positions,
+	/// Proposals the filters ruled out for good
+	skipped: HashSet<ProposalId>,
+}
+
+impl<'a> Maker<'a> {
+	/// Picks up the positions saved in `policy.state`.
+	pub fn new(
+		client: &'a Client<XPredictRuntime>,
+		signer: &'a XPredictSigner,
+		policy: MakerConfig,
+	) -> Result<Self> {
+		Ok(Maker {
+			client,
+			account: signer.account_id().clone(),
+			signer,
+			positions: load_positions(&policy.state)?,
+			policy,
+			skipped: HashSet::new(),
+		})
+	}
+
+	pub fn positions(&self) -> &Positions {
+		&self.positions
+	}
+
+	/// The holdings of `proposal_id` marked to its pool.
+	pub async fn inventory(&self, proposal_id: ProposalId) -> Result<Inventory> {
+		let position = self
+			.positions
+			.get(&proposal_id)
+			.cloned()
+			.unwrap_or_default();
+		let pool = PoolState::fetch(self.client, proposal_id).await?;
+		let shares =
+			XPredictLogic::proposal_liquidity_of(self.client, proposal_id, &self.account).await?;
+		let yes = XPredictLogic::balance_of(self.client, pool.yes, &self.account).await?;
+		let no = XPredictLogic::balance_of(self.client, pool.no, &self.account).await?;
+		Inventory::mark(&position, &pool, shares, yes, no)
+	}
+
+	/// Runs a pass on each new block, handing every action to `on_event`.
+	pub async fn run(&mut self, mut on_event: impl FnMut(&MakerEvent)) -> Result<()> {
+		let mut headers = self.client.subscribe_blocks().await?;
+		self.tick(&mut on_event).await?;
+		while headers.next().await.map_err(subxt::Error::from)?.is_some() {
+			self.tick(&mut on_event).await?;
+		}
+		Ok(())
+	}
+
+	/// One pass over the proposals, saving the positions after every action
+	/// and at the end.
+	pub async fn tick(&mut self, on_event: &mut impl FnMut(&MakerEvent)) -> Result<()> {
+		let now = XPredictLogic::now()?;
+		for proposal_id in 0..XPredictLogic::proposal_count(self.client).await? {
+			if self.skipped.contains(&proposal_id) {
+				continue;
+			}
+			let result = if self.positions.contains_key(&proposal_id) {
+				self.manage(proposal_id, now).await
+			} else {
+				self.enter(proposal_id, now).await
+			};
+			let action = match result {
+				Ok(Some(action)) => {
+					save_positions(&self.policy.state, &self.positions)?;
+					action
+				}
+				Ok(None) => continue,
+				Err(e) => MakerAction::Failed(e.to_string()),
+			};
+			on_event(&MakerEvent {
+				proposal_id,
+				action,
+			});
+		}
+		save_positions(&self.policy.state, &self.positions)
+	}
+
+	/// Adds liquidity if the proposal passes the filters.
+	async fn enter(&mut self, proposal_id: ProposalId, now: Moment) -> Result<Option<MakerAction>> {
+		let client = self.client;
+		let status = XPredictLogic::proposal_status(client, proposal_id).await?;
+		if status != ProposalStatus::FormalPrediction {
+			if status != ProposalStatus::OriginalPrediction {
+				self.skipped.insert(proposal_id);
+			}
+			return Ok(None);
+		}
+		let currency_id = XPredictLogic::proposal_currency(client, proposal_id).await?;
+		let category_id = XPredictLogic::proposal_info(client, proposal_id)
+			.await?
+			.category_id;
+		let close_time = XPredictLogic::proposal_close_time(client, proposal_id).await?;
+		if self.policy.liquidity == 0
+			|| self.closing(close_time, now)
+			|| !matches(&self.policy.currencies, currency_id)
+			|| !matches(&self.policy.categories, category_id)
+		{
+			self.skipped.insert(proposal_id);
+			return Ok(None);
+		}
+		if XPredictLogic::proposal_total_volume(client, proposal_id).await? < self.policy.min_volume
+		{
+			return Ok(None);
+		}
+		let number = self.policy.liquidity;
+		// shares without a position were added before the state was saved,
+		// they are taken over instead of adding again
+		if XPredictLogic::proposal_liquidity_of(client, proposal_id, &self.account).await? > 0 {
+			self.positions.insert(
+				proposal_id,
+				Position {
+					spent: number,
+					..Position::default()
+				},
+			);
+			return Ok(None);
+		}
+		XPredictLogic::proposal_add_liquidity(client, self.signer, proposal_id, number).await?;
+		self.positions.insert(
+			proposal_id,
+			Position {
+				spent: number,
+				..Position::default()
+			},
+		);
+		Ok(Some(MakerAction::AddLiquidity(number)))
+	}
+
+	/// Pulls the liquidity of a closing proposal, or trades its pool toward
+	/// the target probability.
+	async fn manage(
+		&mut self,
+		proposal_id: ProposalId,
+		now: Moment,
+	) -> Result<Option<MakerAction>> {
+		let client = self.client;
+		if self.positions[&proposal_id].exited {
+			return Ok(None);
+		}
+		let status = XPredictLogic::proposal_status(client, proposal_id).await?;
+		let close_time = XPredictLogic::proposal_close_time(client, proposal_id).await?;
+		if status != ProposalStatus::FormalPrediction || self.closing(close_time, now) {
+			let shares =
+				XPredictLogic::proposal_liquidity_of(client, proposal_id, &self.account).await?;
+			let returned = PoolState::fetch(client, proposal_id)
+				.await?
+				.quote_remove_liquidity(shares)?;
+			if shares > 0 {
+				XPredictLogic::proposal_remove_liquidity(client, self.signer, proposal_id, shares)
+					.await?;
+			}
+			// the returned tokens stay in the inventory, the fee reward is paid
+			// out in the proposal currency
+			let position = self.position(proposal_id);
+			position.received += returned.fee_reward;
+			position.exited = true;
+			return Ok(Some(MakerAction::RemoveLiquidity {
+				shares,
+				yes: returned.yes,
+				no: returned.no,
+				fee_reward: returned.fee_reward,
+			}));
+		}
+		match self.policy.target_probability {
+			Some(target) => self.rebalance(proposal_id, target).await,
+			None => Ok(None),
+		}
+	}
+
+	/// Sells yes tokens, or buys no tokens without any, while the yes
+	/// probability is above the target, and buys yes tokens while below, never
+	/// trading past the target.
+	async fn rebalance(
+		&mut self,
+		proposal_id: ProposalId,
+		target: f64,
+	) -> Result<Option<MakerAction>> {
+		let client = self.client;
+		let pool = PoolState::fetch(client, proposal_id).await?;
+		let (yes_probability, _) = pool.probabilities();
+		// the yes tokens held when selling them
+		let (currency_id, bound, selling) = if yes_probability < target - self.policy.tolerance {
+			(pool.yes, target, None)
+		} else if yes_probability > target + self.policy.tolerance {
+			let holding = XPredictLogic::balance_of(client, pool.yes, &self.account).await?;
+			if holding > 0 {
+				(pool.yes, target, Some(holding))
+			} else {
+				(pool.no, 1.0 - target, None)
+			}
+		} else {
+			return Ok(None);
+		};
+		let limit = TradeLimit {
+			min_output: 0,
+			price_bound: Some(bound),
+		};
+		if let Some(holding) = selling {
+			let fits = |number| {
+				pool.quote_sell(currency_id, number)
+					.map_or(false, |quote| quote.price_after >= bound)
+			};
+			// sells returning nothing cannot be quoted either
+			let max = holding.min(self.policy.max_trade);
+			let number = smallest(max, |number| pool.quote_sell(currency_id, number).is_ok())
+				.and_then(|min| largest(min, max, fits));
+			let number = match number {
+				Some(number) => number,
+				None => return Ok(None),
+			};
+			let received = XPredictLogic::sell_with_limit(
+				client,
+				self.signer,
+				proposal_id,
+				currency_id,
+				number,
+				limit,
+			)
+			.await?;
+			self.position(proposal_id).received += received;
+			return Ok(Some(MakerAction::Sell {
+				currency_id,
+				number,
+				received,
+			}));
+		}
+		// buys too small to cover the fees cannot be quoted, the search
+		// starts at the smallest one that can
+		let max = self.policy.max_trade;
+		let fits = |number| {
+			pool.quote_buy(currency_id, number)
+				.map_or(false, |quote| quote.price_after <= bound)
+		};
+		let number = smallest(max, |number| pool.quote_buy(currency_id, number).is_ok())
+			.and_then(|min| largest(min, max, fits));
+		let number = match number {
+			Some(number) => number,
+			None => return Ok(None),
+		};
+		let received = XPredictLogic::buy_with_limit(
+			client,
+			self.signer,
+			proposal_id,
+			currency_id,
+			number,
+			limit,
+		)
+		.await?;
+		self.position(proposal_id).spent += number;
+		Ok(Some(MakerAction::Buy {
+			currency_id,
+			number,
+			received,
+		}))
+	}
+
+	fn closing(&self, close_time: Moment, now: Moment) -> bool {
+		let exit = self.policy.exit_before_close_secs.saturating_mul(1000);
+		now >= close_time.saturating_sub(exit)
+	}
+
+	fn position(&mut self, proposal_id: ProposalId) -> &mut Position {
+		self.positions.entry(proposal_id).or_default()
+	}
+}
+
+fn matches<T: PartialEq>(filter: &[T], value: T) -> bool {
+	filter.is_empty() || filter.contains(&value)
+}
+
+/// Largest number from `min` up to `max` that `fits`, which must hold for
+/// every number from `min` below one it holds for. `None` when `min` does
+/// not fit.
+fn largest(min: Balance, max: Balance, fits: impl Fn(Balance) -> bool) -> Option<Balance> {
+	if min > max || !fits(min) {
+		return None;
+	}
+	let (mut low, mut high) = (min, max);
+	while low < high {
+		let middle = high - (high - low) / 2;
+		if fits(middle) {
+			low = middle;
+		} else {
+			high = middle - 1;
+		}
+	}
+	Some(low)
+}
+
+/// Smallest number up to `max` that `holds`, which must hold for every
+/// number above one it holds for.
+fn smallest(max: Balance, holds: impl Fn(Balance) -> bool) -> Option<Balance> {
+	if !holds(max) {
+		return None;
+	}
+	let (mut low, mut high) = (0, max);
+	while low < high {
+		let middle = low + (high - low) / 2;
+		if holds(middle) {
+			high = middle;
+		} else {
+			low = middle + 1;
+		}
+	}
+	Some(low)
+}
@@ -0,0 +1,89 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+	amm::PoolState,
+	error::{Result, XPredictError},
+	pallets::{Balance, ProposalId},
+};
+
+/// What the maker put into and took out of one proposal, in its currency.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+	pub spent: Balance,
+	pub received: Balance,
+	/// Liquidity was pulled, the position is no longer traded
+	pub exited: bool,
+}
+
+/// A position with its holdings read from the chain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Inventory {
+	pub shares: Balance,
+	pub yes: Balance,
+	pub no: Balance,
+	/// Holdings marked at the pool's implied probabilities, shares as the
+	/// tokens and fees they would return
+	pub value: f64,
+	/// `received + value - spent`
+	pub pnl: f64,
+}
+
+impl Inventory {
+	pub fn mark(
+		position: &Position,
+		pool: &PoolState,
+		shares: Balance,
+		yes: Balance,
+		no: Balance,
+	) -> Result<Self> {
+		let (yes_price, no_price) = pool.probabilities();
+		let liquidity = pool.quote_remove_liquidity(shares)?;
+		let value = (yes + liquidity.yes) as f64 * yes_price
+			+ (no + liquidity.no) as f64 * no_price
+			+ liquidity.fee_reward as f64;
+		Ok(Inventory {
+			shares,
+			yes,
+			no,
+			value,
+			pnl: position.received as f64 + value - position.spent as f64,
+		})
+	}
+
+	pub fn to_json(&self, proposal_id: ProposalId, position: &Position) -> Value {
+		json!({
+			"proposal_id": proposal_id,
+			"spent": position.spent.to_string(),
+			"received": position.received.to_string(),
+			"exited": position.exited,
+			"shares": self.shares.to_string(),
+			"yes": self.yes.to_string(),
+			"no": self.no.to_string(),
+			"value": self.value,
+			"pnl": self.pnl,
+		})
+	}
+}
+
+/// Positions by proposal, kept in a JSON file.
+pub type Positions = BTreeMap<ProposalId, Position>;
+
+pub fn load_positions(path: &Path) -> Result<Positions> {
+	if !path.exists() {
+		return Ok(Positions::new());
+	}
+	let content = fs::read_to_string(path).map_err(|e| state_error(path, e))?;
+	serde_json::from_str(&content).map_err(|e| state_error(path, e))
+}
+
+pub fn save_positions(path: &Path, positions: &Positions) -> Result<()> {
+	let content = serde_json::to_string_pretty(positions).map_err(|e| state_error(path, e))?;
+	fs::write(path, content).map_err(|e| state_error(path, e))
+}
+
+fn state_error(path: &Path, e: impl ToString) -> XPredictError {
+	XPredictError::Maker(format!("{}: {}", path.display(), e.to_string()))
+}
@@ -0,0 +1,266 @@
+//! Daemon uploading the results of proposals waiting for them on behalf of
+//! tagged autonomy accounts, with answers from a pluggable `Resolver`.
+
+mod resolver;
+
+pub use resolver::{CommandResolver, FileResolver, Resolver};
+
+use std::collections::{BTreeSet, HashSet};
+
+use futures::{pin_mut, stream, StreamExt};
+use serde_json::{json, Value};
+use sp_core::Pair;
+use sp_runtime::{
+	traits::{IdentifyAccount, Verify},
+	MultiSignature,
+};
+use subxt::{system::System, Client};
+
+use crate::{
+	error::{Result, XPredictError},
+	events::{subscribe_events, EventRecord, XPredictEvent},
+	logic::XPredictLogic,
+	pallets::{CurrencyId, ProposalId, ProposalStatus},
+	runtime::XPredictRuntime,
+};
+
+type AccountId = <XPredictRuntime as System>::AccountId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+	/// The resolver has no answer yet, asked again every block
+	Unresolved,
+	/// The proposal left `WaitingForResults` before every account uploaded
+	Closed(ProposalStatus),
+	/// `TemporaryResultsStore` already holds the account's answer
+	AlreadyUploaded(CurrencyId),
+	Uploaded(CurrencyId),
+	/// Retried on the next block
+	Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Decision {
+	/// `None` when following the chain failed, the proposals waiting for
+	/// results are then looked up again on the next block
+	pub proposal_id: Option<ProposalId>,
+	/// `None` for decisions about the proposal rather than one account
+	pub account: Option<AccountId>,
+	pub action: Action,
+}
+
+impl Decision {
+	pub fn to_json(&self) -> Value {
+		let (action, detail) = match &self.action {
+			Action::Unresolved => ("unresolved", Value::Null),
+			Action::Closed(status) => ("closed", format!("{:?}", status).into()),
+			Action::AlreadyUploaded(currency_id) => ("already_uploaded", (*currency_id).into()),
+			Action::Uploaded(currency_id) => ("uploaded", (*currency_id).into()),
+			Action::Failed(error) => ("failed", error.as_str().into()),
+		};
+		json!({
+			"proposal_id": self.proposal_id,
+			"account": self.account.as_ref().map(ToString::to_string),
+			"action": action,
+			"detail": detail,
+		})
+	}
+}
+
+type Signer = <MultiSignature as Verify>::Signer;
+
+enum Tick {
+	Event(Result<EventRecord>),
+	Block(Result<()>),
+}
+
+/// Uploads with keys of scheme `P`, the unsigned upload call carries a
+/// signature of its payload rather than an extrinsic signature.
+pub struct Oracle<'a, R, P> {
+	client: &'a Client<XPredictRuntime>,
+	resolver: R,
+	accounts: Vec<(AccountId, &'a P)>,
+	/// Proposals waiting for results some account has not uploaded yet
+	pending: BTreeSet<ProposalId>,
+	/// Proposals already reported as unresolved, to log that once
+	unresolved: HashSet<ProposalId>,
+}
+
+impl<'a, R, P> Oracle<'a, R, P>
+where
+	R: Resolver,
+	P: Pair,
+	MultiSignature: From<P::Signature>,
+	Signer: From<P::Public>,
+{
+	pub fn new(client: &'a Client<XPredictRuntime>, resolver: R, pairs: Vec<&'a P>) -> Self {
+		let accounts = pairs
+			.into_iter()
+			.map(|pair| (Signer::from(pair.public()).into_account(), pair))
+			.collect();
+		Oracle {
+			client,
+			resolver,
+			accounts,
+			pending: BTreeSet::new(),
+			unresolved: HashSet::new(),
+		}
+	}
+
+	/// Picks up the proposals already waiting for results, then follows the
+	/// chain, handing every decision to `on_decision`. Blocks the
+	/// subscription skipped are read too; when reading one fails, the
+	/// proposals waiting for results are looked up again.
+	pub async fn run(&mut self, mut on_decision: impl FnMut(&Decision)) -> Result<()> {
+		for (account, _) in &self.accounts {
+			if XPredictLogic::autonomy_account(self.client, account)
+				.await
+				.is_err()
+			{
+				return Err(XPredictError::Oracle(format!(
+					"{} is not a tagged autonomy account",
+					account
+				)));
+			}
+		}
+		let events = subscribe_events(self.client, false).await?.map(Tick::Event);
+		let headers = self.client.subscribe_blocks().await?;
+		let blocks = stream::unfold(headers, |mut headers| async move {
+			match headers.next().await {
+				Ok(Some(_)) => Some((Tick::Block(Ok(())), headers)),
+				Ok(None) => None,
+				Err(e) => {
+					let error = XPredictError::from(subxt::Error::from(e));
+					Some((Tick::Block(Err(error)), headers))
+				}
+			}
+		});
+		self.scan().await?;
+		self.process(&mut on_decision).await;
+
+		let ticks = stream::select(events, blocks);
+		pin_mut!(ticks);
+		let mut rescan = false;
+		while let Some(tick) = ticks.next().await {
+			let error = match tick {
+				Tick::Event(Ok(record)) => {
+					if let XPredictEvent::ProposalStatusChanged(event) = record.event {
+						if event.state == ProposalStatus::WaitingForResults {
+							self.pending.insert(event.proposal_id);
+						}
+					}
+					continue;
+				}
+				Tick::Event(Err(e)) => Some(e),
+				Tick::Block(Ok(())) if rescan => self.scan().await.err(),
+				Tick::Block(Ok(())) => None,
+				Tick::Block(Err(e)) => Some(e),
+			};
+			match error {
+				Some(e) => {
+					rescan = true;
+					on_decision(&Decision {
+						proposal_id: None,
+						account: None,
+						action: Action::Failed(e.to_string()),
+					});
+				}
+				None => {
+					rescan = false;
+					self.process(&mut on_decision).await;
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Adds every proposal waiting for results to the pending ones.
+	async fn scan(&mut self) -> Result<()> {
+		for proposal_id in 0..XPredictLogic::proposal_count(self.client).await? {
+			let status = XPredictLogic::proposal_status(self.client, proposal_id).await?;
+			if status == ProposalStatus::WaitingForResults {
+				self.pending.insert(proposal_id);
+			}
+		}
+		Ok(())
+	}
+
+	async fn process(&mut self, on_decision: &mut impl FnMut(&Decision)) {
+		let pending: Vec<_> = self.pending.iter().copied().collect();
+		for proposal_id in pending {
+			let done = match self.handle(proposal_id, on_decision).await {
+				Ok(done) => done,
+				Err(e) => {
+					on_decision(&Decision {
+						proposal_id: Some(proposal_id),
+						account: None,
+						action: Action::Failed(e.to_string()),
+					});
+					false
+				}
+			};
+			if done {
+				self.pending.remove(&proposal_id);
+				self.unresolved.remove(&proposal_id);
+			}
+		}
+	}
+
+	/// Uploads the answer of every account that has not yet, returning
+	/// whether the proposal needs no further work.
+	async fn handle(
+		&mut self,
+		proposal_id: ProposalId,
+		on_decision: &mut impl FnMut(&Decision),
+	) -> Result<bool> {
+		let client = self.client;
+		let status = XPredictLogic::proposal_status(client, proposal_id).await?;
+		if status != ProposalStatus::WaitingForResults {
+			on_decision(&Decision {
+				proposal_id: Some(proposal_id),
+				account: None,
+				action: Action::Closed(status),
+			});
+			return Ok(true);
+		}
+		let side = match self.resolver.resolve(proposal_id).await? {
+			Some(side) => side,
+			None => {
+				if self.unresolved.insert(proposal_id) {
+					on_decision(&Decision {
+						proposal_id: Some(proposal_id),
+						account: None,
+						action: Action::Unresolved,
+					});
+				}
+				return Ok(false);
+			}
+		};
+		let currency_id = side.currency(XPredictLogic::proposal_pairs(client, proposal_id).await?);
+		let mut done = true;
+		for (account, pair) in &self.accounts {
+			let uploaded =
+				XPredictLogic::autonomy_temporary_result(client, proposal_id, account).await?;
+			let action = match uploaded {
+				Some(found) => Action::AlreadyUploaded(found),
+				None => {
+					match XPredictLogic::autonomy_upload(client, *pair, proposal_id, currency_id)
+						.await
+					{
+						Ok(()) => Action::Uploaded(currency_id),
+						Err(e) => {
+							done = false;
+							Action::Failed(e.to_string())
+						}
+					}
+				}
+			};
+			on_decision(&Decision {
+				proposal_id: Some(proposal_id),
+				account: Some(account.clone()),
+				action,
+			});
+		}
+		Ok(done)
+	}
+}
@@ -0,0 +1,232 @@
+use std::{
+	collections::BTreeMap,
+	io::Read,
+	path::PathBuf,
+	process::{Command, Stdio},
+	time::{Duration, Instant},
+};
+
+use async_std::{fs, task};
+use async_trait::async_trait;
+
+use crate::{
+	error::{Result, XPredictError},
+	pallets::{ProposalId, Side},
+};
+
+/// How often a running resolver command is checked for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Where the oracle looks up the outcome of a proposal.
+#[async_trait]
+pub trait Resolver: Send + Sync {
+	/// `None` while the outcome is not known yet.
+	async fn resolve(&self, proposal_id: ProposalId) -> Result<Option<Side>>;
+}
+
+#[async_trait]
+impl<R: Resolver + ?Sized> Resolver for Box<R> {
+	async fn resolve(&self, proposal_id: ProposalId) -> Result<Option<Side>> {
+		(**self).resolve(proposal_id).await
+	}
+}
+
+/// Answers from a file read on every lookup, so it can be edited while the
+/// daemon runs. `.csv` files hold `proposal_id,answer` lines, anything else
+/// is a JSON object such as `{"3": "yes", "4": null}`.
+pub struct FileResolver {
+	path: PathBuf,
+}
+
+impl FileResolver {
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		FileResolver { path: path.into() }
+	}
+
+	async fn answers(&self) -> Result<BTreeMap<ProposalId, Option<String>>> {
+		let content = fs::read_to_string(&self.path)
+			.await
+			.map_err(|e| self.error(e))?;
+		if self.path.extension().map_or(false, |ext| ext == "csv") {
+			return Ok(parse_csv(&content));
+		}
+		let answers: BTreeMap<String, Option<String>> =
+			serde_json::from_str(&content).map_err(|e| self.error(e))?;
+		answers
+			.into_iter()
+			.map(|(id, answer)| {
+				let id = id
+					.parse()
+					.map_err(|_| self.error(format!("invalid proposal id {:?}", id)))?;
+				Ok((id, answer))
+			})
+			.collect()
+	}
+
+	fn error(&self, e: impl ToString) -> XPredictError {
+		XPredictError::Oracle(format!("{}: {}", self.path.display(), e.to_string()))
+	}
+}
+
+#[async_trait]
+impl Resolver for FileResolver {
+	async fn resolve(&self, proposal_id: ProposalId) -> Result<Option<Side>> {
+		match self.answers().await?.remove(&proposal_id).flatten() {
+			Some(answer) => parse_answer(&answer),
+			None => Ok(None),
+		}
+	}
+}
+
+/// Lines that do not start with a proposal id, such as a header or `#`
+/// comments, are ignored.
+fn parse_csv(content: &str) -> BTreeMap<ProposalId, Option<String>> {
+	content
+		.lines()
+		.filter_map(|line| {
+			let (id, answer) = line.split_once(',')?;
+			Some((id.trim().parse().ok()?, Some(answer.trim().to_string())))
+		})
+		.collect()
+}
+
+/// Runs `program args... <proposal_id>` and reads `yes` or `no` from its
+/// standard output, nothing meaning not known yet. The program is killed
+/// once it runs longer than `timeout`.
+pub struct CommandResolver {
+	program: String,
+	args: Vec<String>,
+	timeout: Duration,
+}
+
+impl CommandResolver {
+	pub fn new(program: impl Into<String>, args: Vec<String>, timeout: Duration) -> Self {
+		CommandResolver {
+			program: program.into(),
+			args,
+			timeout,
+		}
+	}
+
+	/// Splits `command` into the program and its arguments like a shell
+	/// would, honouring single and double quotes and backslash escapes.
+	pub fn parse(command: &str, timeout: Duration) -> Result<Self> {
+		let mut words = split_command(command)?.into_iter();
+		let program = words
+			.next()
+			.ok_or_else(|| XPredictError::Oracle("empty command".into()))?;
+		Ok(CommandResolver::new(program, words.collect(), timeout))
+	}
+
+	fn error(&self, e: impl ToString) -> XPredictError {
+		XPredictError::Oracle(format!("{}: {}", self.program, e.to_string()))
+	}
+}
+
+#[async_trait]
+impl Resolver for CommandResolver {
+	async fn resolve(&self, proposal_id: ProposalId) -> Result<Option<Side>> {
+		let mut child = Command::new(&self.program)
+			.args(&self.args)
+			.arg(proposal_id.to_string())
+			.stdin(Stdio::null())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped())
+			.spawn()
+			.map_err(|e| self.error(e))?;
+		// polled rather than waited on, so the executor is not blocked
+		let started = Instant::now();
+		let status = loop {
+			if let Some(status) = child.try_wait().map_err(|e| self.error(e))? {
+				break status;
+			}
+			if started.elapsed() >= self.timeout {
+				let _ = child.kill();
+				let _ = child.wait();
+				return Err(self.error(format!("timed out after {:?}", self.timeout)));
+			}
+			task::sleep(POLL_INTERVAL).await;
+		};
+		let (mut stdout, mut stderr) = (String::new(), String::new());
+		if let Some(pipe) = child.stdout.as_mut() {
+			pipe.read_to_string(&mut stdout)
+				.map_err(|e| self.error(e))?;
+		}
+		if !status.success() {
+			if let Some(pipe) = child.stderr.as_mut() {
+				pipe.read_to_string(&mut stderr)
+					.map_err(|e| self.error(e))?;
+			}
+			return Err(self.error(format!("exited with {}: {}", status, stderr.trim())));
+		}
+		parse_answer(&stdout)
+	}
+}
+
+/// Words of a command line, `'...'` taken literally, `"..."` and bare words
+/// with `\` escaping the next character.
+fn split_command(command: &str) -> Result<Vec<String>> {
+	let mut words = Vec::new();
+	let mut word: Option<String> = None;
+	let mut quote = None;
+	let mut chars = command.chars();
+	while let Some(c) = chars.next() {
+		match (quote, c) {
+			(Some('\''), '\'') | (Some('"'), '"') => quote = None,
+			(Some('\''), c) => word.get_or_insert_with(String::new).push(c),
+			(_, '\\') => {
+				let escaped = chars
+					.next()
+					.ok_or_else(|| XPredictError::Oracle("command ends with \\".into()))?;
+				word.get_or_insert_with(String::new).push(escaped);
+			}
+			(Some(_), c) => word.get_or_insert_with(String::new).push(c),
+			(None, '\'') | (None, '"') => {
+				quote = Some(c);
+				word.get_or_insert_with(String::new);
+			}
+			(None, c) if c.is_whitespace() => words.extend(word.take()),
+			(None, c) => word.get_or_insert_with(String::new).push(c),
+		}
+	}
+	if quote.is_some() {
+		return Err(XPredictError::Oracle(format!(
+			"unterminated quote in {:?}",
+			command
+		)));
+	}
+	words.extend(word);
+	Ok(words)
+}
+
+fn parse_answer(answer: &str) -> Result<Option<Side>> {
+	if answer.trim().is_empty() {
+		return Ok(None);
+	}
+	answer.parse().map(Some).map_err(XPredictError::Oracle)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn splits_quoted_words() {
+		let words = split_command(r#"python3 'my script.py' --name "a \"b\"" c\ d ''"#).unwrap();
+		assert_eq!(
+			words,
+			vec!["python3", "my script.py", "--name", "a \"b\"", "c d", ""]
+		);
+	}
+
+	#[test]
+	fn rejects_unterminated_quotes() {
+		assert!(split_command("resolve 'oops").is_err());
+		assert!(split_command("resolve oops\\").is_err());
+	}
+
+	#[test]
+	fn empty_command_has_no_program() {
+		assert!(CommandResolver::parse("   ", Duration::from_secs(1)).is_err());
+	}
+}
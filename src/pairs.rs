@@ -1,102 +1,125 @@
 use std::{collections::HashMap, fmt::Debug, slice::Iter};
 
+use serde::Deserialize;
 use sp_core::Pair;
-use sp_keyring::AccountKeyring;
 use sp_runtime::traits::{IdentifyAccount, Verify};
-use subxt::{sp_core::sr25519::Pair as Sr25519Pair, PairSigner, Runtime};
+use subxt::{sp_core::sr25519::Pair as Sr25519Pair, PairSigner, Runtime, Signer};
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+use crate::{
+	config::KeysConfig,
+	error::{Result, XPredictError},
+	keystore::{EncryptedKeystore, SchemePair},
+	runtime::XPredictRuntime,
+};
+
+/// Any signer the runtime accepts, whatever its key scheme.
+pub type XPredictSigner = dyn Signer<XPredictRuntime> + Send + Sync;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PairAuthority {
 	Admin,
 	Normal,
 }
 
-pub trait XPredictPairs<T>
+pub trait XPredictPairs<T, P = Sr25519Pair>
 where
 	T: Runtime,
-	T::Signature: From<<Sr25519Pair as Pair>::Signature>,
-	<T::Signature as Verify>::Signer:
-		From<<Sr25519Pair as Pair>::Public> + IdentifyAccount<AccountId = T::AccountId>,
+	P: Pair,
+	T::Signature: From<P::Signature>,
+	<T::Signature as Verify>::Signer: From<P::Public> + IdentifyAccount<AccountId = T::AccountId>,
 {
-	fn get_pair_iter(&self, authority: PairAuthority) -> Option<Iter<Sr25519Pair>>;
-	fn get_pair_signer(
-		&self,
-		authority: PairAuthority,
-		index: usize,
-	) -> Option<PairSigner<T, Sr25519Pair>>;
-	fn get_default_signer(&self, authority: PairAuthority) -> Option<PairSigner<T, Sr25519Pair>>;
-	fn get_public(
-		&self,
-		authority: PairAuthority,
-		index: usize,
-	) -> Option<<Sr25519Pair as Pair>::Public>;
-	fn get_signer(pair: &Sr25519Pair) -> PairSigner<T, Sr25519Pair>;
+	fn get_pair_iter(&self, authority: PairAuthority) -> Option<Iter<P>>;
+	fn get_pair_signer(&self, authority: PairAuthority, index: usize) -> Option<PairSigner<T, P>>;
+	fn get_default_signer(&self, authority: PairAuthority) -> Option<PairSigner<T, P>>;
+	fn get_public(&self, authority: PairAuthority, index: usize) -> Option<P::Public>;
+	fn get_signer(pair: &P) -> PairSigner<T, P>;
 }
 
-pub struct XPredictKeystore {
-	pairs_map: HashMap<PairAuthority, Vec<Sr25519Pair>>,
+pub struct XPredictKeystore<P = Sr25519Pair> {
+	pairs_map: HashMap<PairAuthority, Vec<P>>,
+}
+
+impl<P> Default for XPredictKeystore<P> {
+	fn default() -> Self {
+		XPredictKeystore {
+			pairs_map: HashMap::new(),
+		}
+	}
+}
+
+impl<P: SchemePair> XPredictKeystore<P> {
+	/// Loads the configured keys of scheme `P`, including the ones of that
+	/// scheme in the encrypted keystore.
+	pub fn from_config(config: &KeysConfig) -> Result<XPredictKeystore<P>> {
+		let mut keystore = XPredictKeystore::default();
+		let (admin, normal) = config.sources(P::SCHEME);
+		for (authority, sources) in [
+			(PairAuthority::Admin, admin),
+			(PairAuthority::Normal, normal),
+		] {
+			for source in sources {
+				keystore.insert(authority, source.load()?);
+			}
+		}
+		if let Some(path) = &config.keystore {
+			let password = std::env::var(&config.keystore_password_env).map_err(|_| {
+				XPredictError::Keystore(format!(
+					"password variable {} is not set",
+					config.keystore_password_env
+				))
+			})?;
+			let mut encrypted = EncryptedKeystore::<P>::open(path)?;
+			encrypted.unlock(&password)?;
+			keystore.merge(encrypted.into_unlocked());
+		}
+		Ok(keystore)
+	}
 }
 
-impl XPredictKeystore {
-	pub fn new() -> XPredictKeystore {
-		let mut pairs_map = HashMap::new();
-		pairs_map.insert(PairAuthority::Admin, vec![AccountKeyring::Alice.pair()]);
-		pairs_map.insert(
-			PairAuthority::Normal,
-			vec![
-				AccountKeyring::Bob.pair(),
-				AccountKeyring::Charlie.pair(),
-				AccountKeyring::Dave.pair(),
-				AccountKeyring::Eve.pair(),
-				AccountKeyring::Ferdie.pair(),
-				AccountKeyring::One.pair(),
-				AccountKeyring::Two.pair(),
-			],
-		);
-		XPredictKeystore { pairs_map }
+impl<P> XPredictKeystore<P> {
+	pub fn insert(&mut self, authority: PairAuthority, pair: P) {
+		self.pairs_map.entry(authority).or_default().push(pair);
+	}
+
+	pub fn merge(&mut self, other: XPredictKeystore<P>) {
+		for (authority, pairs) in other.pairs_map {
+			self.pairs_map.entry(authority).or_default().extend(pairs);
+		}
+	}
+
+	pub fn get_pair(&self, authority: PairAuthority, index: usize) -> Option<&P> {
+		self.pairs_map
+			.get(&authority)
+			.and_then(|pairs| pairs.get(index))
 	}
 }
 
-impl<T> XPredictPairs<T> for XPredictKeystore
+impl<T, P> XPredictPairs<T, P> for XPredictKeystore<P>
 where
 	T: Runtime,
-	T::Signature: From<<Sr25519Pair as Pair>::Signature>,
-	<T::Signature as Verify>::Signer:
-		From<<Sr25519Pair as Pair>::Public> + IdentifyAccount<AccountId = T::AccountId>,
+	P: Pair,
+	T::Signature: From<P::Signature>,
+	<T::Signature as Verify>::Signer: From<P::Public> + IdentifyAccount<AccountId = T::AccountId>,
 {
-	fn get_pair_iter(&self, authority: PairAuthority) -> Option<Iter<Sr25519Pair>> {
+	fn get_pair_iter(&self, authority: PairAuthority) -> Option<Iter<P>> {
 		self.pairs_map.get(&authority).map(|pairs| pairs.iter())
 	}
 
-	fn get_pair_signer(
-		&self,
-		authority: PairAuthority,
-		index: usize,
-	) -> Option<PairSigner<T, Sr25519Pair>> {
-		if let Some(pairs) = self.pairs_map.get(&authority) {
-			pairs.get(index).map(|pair| Self::get_signer(pair))
-		} else {
-			None
-		}
+	fn get_pair_signer(&self, authority: PairAuthority, index: usize) -> Option<PairSigner<T, P>> {
+		self.get_pair(authority, index)
+			.map(|pair| <Self as XPredictPairs<T, P>>::get_signer(pair))
 	}
 
-	fn get_default_signer(&self, authority: PairAuthority) -> Option<PairSigner<T, Sr25519Pair>> {
-		self.get_pair_signer(authority, 0)
+	fn get_default_signer(&self, authority: PairAuthority) -> Option<PairSigner<T, P>> {
+		<Self as XPredictPairs<T, P>>::get_pair_signer(self, authority, 0)
 	}
 
-	fn get_public(
-		&self,
-		authority: PairAuthority,
-		index: usize,
-	) -> Option<<Sr25519Pair as Pair>::Public> {
-		if let Some(pairs) = self.pairs_map.get(&authority) {
-			pairs.get(index).map(|pair| pair.public())
-		} else {
-			None
-		}
+	fn get_public(&self, authority: PairAuthority, index: usize) -> Option<P::Public> {
+		self.get_pair(authority, index).map(|pair| pair.public())
 	}
 
-	fn get_signer(pair: &Sr25519Pair) -> PairSigner<T, Sr25519Pair> {
+	fn get_signer(pair: &P) -> PairSigner<T, P> {
 		PairSigner::new(pair.clone())
 	}
 }
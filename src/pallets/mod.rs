@@ -3,8 +3,12 @@ pub mod couple;
 pub mod proposals;
 pub mod ruler;
 pub mod tokens;
+pub mod utility;
+
+use std::str::FromStr;
 
 use codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
 
 pub type CurrencyId = u32;
 pub type Balance = u128;
@@ -21,7 +25,46 @@ pub enum RulerModule {
 	NotUsed,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+impl RulerModule {
+	pub const ALL: [RulerModule; 3] = [
+		RulerModule::PlatformDividend,
+		RulerModule::CrossChainBurn,
+		RulerModule::NotUsed,
+	];
+}
+
+/// One of the two outcomes of a proposal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+	Yes,
+	No,
+}
+
+impl Side {
+	/// The token of this side out of the `(yes, no)` pool pairs.
+	pub fn currency(self, (yes, no): (CurrencyId, CurrencyId)) -> CurrencyId {
+		match self {
+			Side::Yes => yes,
+			Side::No => no,
+		}
+	}
+}
+
+impl FromStr for Side {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.trim().to_lowercase().as_str() {
+			"yes" => Ok(Side::Yes),
+			"no" => Ok(Side::No),
+			_ => Err(format!("unknown side {:?}, use yes or no", s)),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ProposalStatus {
 	FormalPrediction,
 	OriginalPrediction,
@@ -0,0 +1,40 @@
+use std::marker::PhantomData;
+
+use codec::{Decode, Encode, Output};
+use sp_runtime::DispatchError;
+use subxt::{module, system::System, Call, Event};
+
+#[module]
+pub trait Utility: System {}
+
+/// A call already encoded with `Client::encode`, written out as is.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RawCall(pub Vec<u8>);
+
+impl Encode for RawCall {
+	fn size_hint(&self) -> usize {
+		self.0.len()
+	}
+
+	fn encode_to<W: Output + ?Sized>(&self, dest: &mut W) {
+		dest.write(&self.0)
+	}
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Call, Encode)]
+pub struct BatchCall<T: Utility> {
+	pub calls: Vec<RawCall>,
+	pub _runtime: PhantomData<T>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Event, Decode)]
+pub struct BatchInterruptedEvent<T: Utility> {
+	pub index: u32,
+	pub error: DispatchError,
+	pub _runtime: PhantomData<T>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Event, Decode)]
+pub struct BatchCompletedEvent<T: Utility> {
+	pub _runtime: PhantomData<T>,
+}
@@ -0,0 +1,211 @@
+//! Holdings of one account across every currency and proposal, marked to
+//! market at the pools' implied prices.
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+use subxt::{system::System, Client};
+
+use crate::{
+	amm::{LiquidityQuote, PoolState},
+	error::Result,
+	logic::XPredictLogic,
+	pallets::{Balance, CurrencyId, ProposalId, ProposalStatus},
+	runtime::XPredictRuntime,
+};
+
+type AccountId = <XPredictRuntime as System>::AccountId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Holding {
+	pub free: Balance,
+	pub reserved: Balance,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProposalPosition {
+	pub proposal_id: ProposalId,
+	pub status: ProposalStatus,
+	/// Currency the pool trades against and the position is valued in
+	pub currency_id: CurrencyId,
+	pub yes: (CurrencyId, Balance),
+	pub no: (CurrencyId, Balance),
+	/// Liquidity shares and the tokens and fees they would return
+	pub shares: Balance,
+	pub share_yes: Balance,
+	pub share_no: Balance,
+	pub share_fee: Balance,
+	pub pending_reward: Balance,
+	/// Staked number and opinion while the proposal was voted on
+	pub vote_stake: Option<(Balance, bool)>,
+	/// 1 and 0 once the result is set, the implied probabilities before
+	pub yes_price: f64,
+	pub no_price: f64,
+	/// Tokens, shares and pending reward in `currency_id`, vote stake aside
+	pub value: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Portfolio {
+	pub account: AccountId,
+	/// Non-zero balances, currency 0 being the native one
+	pub holdings: BTreeMap<CurrencyId, Holding>,
+	/// Stake for uploading results
+	pub autonomy_stake: Balance,
+	/// Proposals the account has tokens, shares, rewards or a vote stake in
+	pub proposals: Vec<ProposalPosition>,
+	/// Proposals that could not be read, left out of `proposals`
+	pub errors: Vec<(ProposalId, String)>,
+}
+
+impl Portfolio {
+	pub async fn fetch(client: &Client<XPredictRuntime>, account: &AccountId) -> Result<Self> {
+		let mut holdings = BTreeMap::new();
+		for currency_id in 0..XPredictLogic::number_of_currency(client).await? {
+			let holding = Holding {
+				free: XPredictLogic::balance_of(client, currency_id, account).await?,
+				reserved: XPredictLogic::reserve_of(client, currency_id, account).await?,
+			};
+			if holding.free > 0 || holding.reserved > 0 {
+				holdings.insert(currency_id, holding);
+			}
+		}
+		let mut proposals = Vec::new();
+		let mut errors = Vec::new();
+		for proposal_id in 0..XPredictLogic::proposal_count(client).await? {
+			match position(client, account, proposal_id, &holdings).await {
+				Ok(Some(position)) => proposals.push(position),
+				Ok(None) => {}
+				Err(e) => errors.push((proposal_id, e.to_string())),
+			}
+		}
+		Ok(Portfolio {
+			account: account.clone(),
+			holdings,
+			autonomy_stake: XPredictLogic::autonomy_stake_account(client, account).await?,
+			proposals,
+			errors,
+		})
+	}
+
+	/// Marked value of the proposal positions by the currency they are in.
+	pub fn value_by_currency(&self) -> BTreeMap<CurrencyId, f64> {
+		let mut values = BTreeMap::new();
+		for position in &self.proposals {
+			*values.entry(position.currency_id).or_insert(0.0) += position.value;
+		}
+		values
+	}
+
+	pub fn to_json(&self) -> Value {
+		let holdings: BTreeMap<_, _> = self
+			.holdings
+			.iter()
+			.map(|(currency_id, holding)| {
+				let value = json!({
+					"free": holding.free.to_string(),
+					"reserved": holding.reserved.to_string(),
+				});
+				(currency_id.to_string(), value)
+			})
+			.collect();
+		let values: BTreeMap<_, _> = self
+			.value_by_currency()
+			.into_iter()
+			.map(|(currency_id, value)| (currency_id.to_string(), value))
+			.collect();
+		json!({
+			"account": self.account.to_string(),
+			"holdings": holdings,
+			"autonomy_stake": self.autonomy_stake.to_string(),
+			"proposals": self.proposals.iter().map(ProposalPosition::to_json).collect::<Vec<_>>(),
+			"value": values,
+			"errors": self
+				.errors
+				.iter()
+				.map(|(proposal_id, error)| json!({ "proposal_id": proposal_id, "error": error }))
+				.collect::<Vec<_>>(),
+		})
+	}
+}
+
+impl ProposalPosition {
+	pub fn to_json(&self) -> Value {
+		json!({
+			"proposal_id": self.proposal_id,
+			"status": format!("{:?}", self.status),
+			"currency_id": self.currency_id,
+			"yes": {
+				"currency_id": self.yes.0,
+				"number": self.yes.1.to_string(),
+				"price": self.yes_price,
+			},
+			"no": {
+				"currency_id": self.no.0,
+				"number": self.no.1.to_string(),
+				"price": self.no_price,
+			},
+			"liquidity": {
+				"shares": self.shares.to_string(),
+				"yes": self.share_yes.to_string(),
+				"no": self.share_no.to_string(),
+				"fee": self.share_fee.to_string(),
+			},
+			"pending_reward": self.pending_reward.to_string(),
+			"vote_stake": self.vote_stake.map(|(number, opinion)| json!({
+				"number": number.to_string(),
+				"opinion": if opinion { "yes" } else { "no" },
+			})),
+			"value": self.value,
+		})
+	}
+}
+
+/// `None` when the account has nothing in the proposal.
+async fn position(
+	client: &Client<XPredictRuntime>,
+	account: &AccountId,
+	proposal_id: ProposalId,
+	holdings: &BTreeMap<CurrencyId, Holding>,
+) -> Result<Option<ProposalPosition>> {
+	let pool = PoolState::fetch(client, proposal_id).await?;
+	let held = |currency_id| holdings.get(&currency_id).map_or(0, |holding| holding.free);
+	let (yes, no) = (held(pool.yes), held(pool.no));
+	let shares = XPredictLogic::proposal_liquidity_of(client, proposal_id, account).await?;
+	let pending_reward =
+		XPredictLogic::proposal_pending_autonomy_reward(client, proposal_id, account).await?;
+	let vote_stake = XPredictLogic::proposal_vote_stake(client, proposal_id, account).await?;
+	if yes == 0 && no == 0 && shares == 0 && pending_reward == 0 && vote_stake.is_none() {
+		return Ok(None);
+	}
+	let (yes_price, no_price) = match XPredictLogic::proposal_result(client, proposal_id).await? {
+		Some(result) if result == pool.yes => (1.0, 0.0),
+		Some(_) => (0.0, 1.0),
+		None => pool.probabilities(),
+	};
+	let share = if shares == 0 {
+		LiquidityQuote::default()
+	} else {
+		pool.quote_remove_liquidity(shares)?
+	};
+	let value = (yes + share.yes) as f64 * yes_price
+		+ (no + share.no) as f64 * no_price
+		+ share.fee_reward as f64
+		+ pending_reward as f64;
+	Ok(Some(ProposalPosition {
+		proposal_id,
+		status: XPredictLogic::proposal_status(client, proposal_id).await?,
+		currency_id: XPredictLogic::proposal_currency(client, proposal_id).await?,
+		yes: (pool.yes, yes),
+		no: (pool.no, no),
+		shares,
+		share_yes: share.yes,
+		share_no: share.no,
+		share_fee: share.fee_reward,
+		pending_reward,
+		vote_stake,
+		yes_price,
+		no_price,
+		value,
+	}))
+}
@@ -1,5 +1,6 @@
 use crate::pallets::{
-	autonomy::Autonomy, couple::Couple, proposals::Proposals, ruler::Ruler, tokens::Tokens, *,
+	autonomy::Autonomy, couple::Couple, proposals::Proposals, ruler::Ruler, tokens::Tokens,
+	utility::Utility, *,
 };
 use sp_runtime::{
 	generic::Header,
@@ -78,3 +79,5 @@ impl Couple for XPredictRuntime {}
 impl Proposals for XPredictRuntime {}
 
 impl Ruler for XPredictRuntime {}
+
+impl Utility for XPredictRuntime {}
@@ -0,0 +1,292 @@
+//! Declarative end-to-end scenarios: accounts, funding, proposals, trades,
+//! waits and assertions read from a TOML file and run step by step.
+
+mod runner;
+
+pub use runner::{Runner, ScenarioReport, StepOutcome, StepReport};
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{
+	error::{Result, XPredictError},
+	pairs::PairAuthority,
+	pallets::{Balance, ProposalStatus, Side},
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+	pub name: String,
+	/// Names the steps use for keys of the keystore
+	#[serde(default)]
+	pub accounts: BTreeMap<String, AccountRef>,
+	pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountRef {
+	pub authority: PairAuthority,
+	#[serde(default)]
+	pub index: usize,
+}
+
+/// A currency or proposal id, or the name a previous step saved one under.
+/// Saving proposal `p` also saves its outcome tokens as `p.yes` and `p.no`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Id {
+	Literal(u32),
+	Variable(String),
+}
+
+/// A balance, either a TOML integer or a string such as `"3125e6"` for
+/// numbers past the TOML integer range.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Amount {
+	Integer(u64),
+	Text(String),
+}
+
+impl Amount {
+	pub fn value(&self) -> Result<Balance> {
+		let text = match self {
+			Amount::Integer(value) => return Ok((*value).into()),
+			Amount::Text(text) => text.trim(),
+		};
+		let invalid = || XPredictError::Scenario(format!("invalid amount {:?}", text));
+		let (mantissa, exponent) = match text.split_once(|c| c == 'e' || c == 'E') {
+			Some((mantissa, exponent)) => (mantissa, exponent.parse().map_err(|_| invalid())?),
+			None => (text, 0),
+		};
+		let mantissa: Balance = mantissa.replace('_', "").parse().map_err(|_| invalid())?;
+		10u128
+			.checked_pow(exponent)
+			.and_then(|scale| mantissa.checked_mul(scale))
+			.ok_or_else(invalid)
+	}
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Step {
+	/// Create an asset (sudo) and save its currency id
+	NewAsset {
+		signer: String,
+		decimals: u8,
+		save: String,
+	},
+	/// Mint `amount` to `to` (sudo)
+	Mint {
+		signer: String,
+		currency: Id,
+		to: String,
+		amount: Amount,
+	},
+	/// Transfer whatever `to` lacks of `amount`, nothing if it already has it
+	Fund {
+		signer: String,
+		currency: Id,
+		to: String,
+		amount: Amount,
+	},
+	/// Stake `account` for uploading results unless it already is, first
+	/// topping it up to `MinimalStakeNumber` of the stake currency from
+	/// `fund_from`
+	AutonomyStake {
+		account: String,
+		fund_from: Option<String>,
+		/// Stake currency, read from the autonomy pallet when unset
+		currency: Option<Id>,
+	},
+	/// Tag `account` as a result uploader (sudo) unless it already is
+	AutonomyTag {
+		signer: String,
+		account: String,
+	},
+	/// Create a proposal and save its id, config values fill the defaults
+	NewProposal {
+		signer: String,
+		currency: Id,
+		number: Amount,
+		close_after_secs: Option<u64>,
+		earn_fee: Option<u32>,
+		save: String,
+	},
+	/// Force a proposal to a status (sudo)
+	SetStatus {
+		signer: String,
+		proposal: Id,
+		status: ProposalStatus,
+	},
+	Buy {
+		signer: String,
+		proposal: Id,
+		side: Side,
+		number: Amount,
+		min_output: Option<Amount>,
+	},
+	Sell {
+		signer: String,
+		proposal: Id,
+		side: Side,
+		number: Amount,
+		min_output: Option<Amount>,
+	},
+	AddLiquidity {
+		signer: String,
+		proposal: Id,
+		number: Amount,
+	},
+	RemoveLiquidity {
+		signer: String,
+		proposal: Id,
+		number: Amount,
+	},
+	/// Wait for a status, `timeout_secs` defaults to the config value
+	WaitStatus {
+		proposal: Id,
+		status: ProposalStatus,
+		timeout_secs: Option<u64>,
+	},
+	Sleep {
+		secs: u64,
+	},
+	UploadResult {
+		account: String,
+		proposal: Id,
+		side: Side,
+	},
+	/// Set the result of a proposal (sudo)
+	SetResult {
+		signer: String,
+		proposal: Id,
+		side: Side,
+	},
+	/// Check the free balance of `account` against inclusive bounds
+	AssertBalance {
+		account: String,
+		currency: Id,
+		min: Option<Amount>,
+		max: Option<Amount>,
+	},
+	AssertStatus {
+		proposal: Id,
+		status: ProposalStatus,
+	},
+}
+
+impl Step {
+	pub fn action(&self) -> &'static str {
+		match self {
+			Step::NewAsset { .. } => "new_asset",
+			Step::Mint { .. } => "mint",
+			Step::Fund { .. } => "fund",
+			Step::AutonomyStake { .. } => "autonomy_stake",
+			Step::AutonomyTag { .. } => "autonomy_tag",
+			Step::NewProposal { .. } => "new_proposal",
+			Step::SetStatus { .. } => "set_status",
+			Step::Buy { .. } => "buy",
+			Step::Sell { .. } => "sell",
+			Step::AddLiquidity { .. } => "add_liquidity",
+			Step::RemoveLiquidity { .. } => "remove_liquidity",
+			Step::WaitStatus { .. } => "wait_status",
+			Step::Sleep { .. } => "sleep",
+			Step::UploadResult { .. } => "upload_result",
+			Step::SetResult { .. } => "set_result",
+			Step::AssertBalance { .. } => "assert_balance",
+			Step::AssertStatus { .. } => "assert_status",
+		}
+	}
+
+	/// Assertions are reported and the scenario goes on, other failures
+	/// skip the remaining steps.
+	pub fn is_assertion(&self) -> bool {
+		matches!(self, Step::AssertBalance { .. } | Step::AssertStatus { .. })
+	}
+}
+
+impl Scenario {
+	pub fn from_file(path: &Path) -> Result<Self> {
+		let content = fs::read_to_string(path)
+			.map_err(|e| XPredictError::Scenario(format!("{}: {}", path.display(), e)))?;
+		toml::from_str(&content)
+			.map_err(|e| XPredictError::Scenario(format!("{}: {}", path.display(), e)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn text(text: &str) -> Amount {
+		Amount::Text(text.into())
+	}
+
+	#[test]
+	fn reads_amounts() {
+		assert_eq!(Amount::Integer(42).value().unwrap(), 42);
+		assert_eq!(text("3125e6").value().unwrap(), 3_125_000_000);
+		assert_eq!(text(" 10E12 ").value().unwrap(), 10_000_000_000_000);
+		assert_eq!(text("1_000_000").value().unwrap(), 1_000_000);
+		assert_eq!(text("1_000e3").value().unwrap(), 1_000_000);
+		assert_eq!(text("0e38").value().unwrap(), 0);
+	}
+
+	#[test]
+	fn reads_amounts_past_the_toml_integer_range() {
+		assert_eq!(text("1e38").value().unwrap(), 10u128.pow(38));
+		assert_eq!(text(&u128::MAX.to_string()).value().unwrap(), u128::MAX);
+	}
+
+	#[test]
+	fn rejects_invalid_amounts() {
+		for invalid in [
+			"",
+			"e6",
+			"1.5e6",
+			"-1",
+			"1e-3",
+			"1e",
+			"ten",
+			"1e39",
+			"4e38",
+			"340282366920938463463374607431768211456",
+		] {
+			assert!(
+				matches!(text(invalid).value(), Err(XPredictError::Scenario(_))),
+				"{:?} was accepted",
+				invalid
+			);
+		}
+	}
+
+	#[test]
+	fn parses_the_demo() {
+		let scenario: Scenario = toml::from_str(include_str!("../../scenarios/demo.toml")).unwrap();
+		assert_eq!(scenario.name, "demo");
+		assert_eq!(scenario.accounts.len(), 6);
+		assert_eq!(scenario.accounts["ferdie"].authority, PairAuthority::Normal);
+		assert_eq!(scenario.accounts["ferdie"].index, 4);
+		assert_eq!(scenario.steps.len(), 24);
+		assert_eq!(scenario.steps[0].action(), "new_asset");
+		for step in &scenario.steps {
+			if let Step::Mint { amount, .. } | Step::Fund { amount, .. } = step {
+				amount.value().unwrap();
+			}
+		}
+	}
+
+	#[test]
+	fn rejects_unknown_fields() {
+		let content =
+			"name = \"x\"\nsteps = []\n[accounts]\nbob = { authority = \"normal\", idx = 1 }";
+		assert!(toml::from_str::<Scenario>(content).is_err());
+		assert!(
+			toml::from_str::<Scenario>("name = \"x\"\nsteps = [{ action = \"jump\" }]").is_err()
+		);
+	}
+}
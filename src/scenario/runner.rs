@@ -0,0 +1,493 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+use sp_core::Pair;
+use subxt::{sp_core::sr25519::Pair as Sr25519Pair, system::System, Client, PairSigner};
+
+use super::{AccountRef, Amount, Id, Scenario, Step};
+use crate::{
+	amm::TradeLimit,
+	config::XPredictConfig,
+	error::{Result, XPredictError},
+	logic::XPredictLogic,
+	pairs::{XPredictKeystore, XPredictPairs},
+	pallets::{CurrencyId, ProposalId, Side},
+	runtime::XPredictRuntime,
+};
+
+type AccountId = <XPredictRuntime as System>::AccountId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepOutcome {
+	Passed(String),
+	Failed(String),
+	/// Not run because an earlier action failed
+	Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepReport {
+	/// Position in the scenario, starting at 1
+	pub index: usize,
+	pub action: &'static str,
+	pub outcome: StepOutcome,
+	pub elapsed: Duration,
+}
+
+impl StepReport {
+	pub fn passed(&self) -> bool {
+		matches!(self.outcome, StepOutcome::Passed(_))
+	}
+
+	pub fn to_json(&self) -> Value {
+		let (result, detail) = match &self.outcome {
+			StepOutcome::Passed(detail) => ("pass", detail.as_str()),
+			StepOutcome::Failed(detail) => ("fail", detail.as_str()),
+			StepOutcome::Skipped => ("skip", ""),
+		};
+		json!({
+			"step": self.index,
+			"action": self.action,
+			"result": result,
+			"detail": detail,
+			"elapsed_ms": self.elapsed.as_millis() as u64,
+		})
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct ScenarioReport {
+	pub name: String,
+	pub steps: Vec<StepReport>,
+}
+
+impl ScenarioReport {
+	pub fn passed(&self) -> bool {
+		self.steps.iter().all(StepReport::passed)
+	}
+
+	pub fn failed(&self) -> usize {
+		self.steps
+			.iter()
+			.filter(|step| matches!(step.outcome, StepOutcome::Failed(_)))
+			.count()
+	}
+}
+
+/// Runs scenarios against a node with the sr25519 keys of `keystore`.
+pub struct Runner<'a> {
+	client: &'a Client<XPredictRuntime>,
+	keystore: &'a XPredictKeystore,
+	config: &'a XPredictConfig,
+	accounts: BTreeMap<String, AccountRef>,
+	variables: HashMap<String, u32>,
+}
+
+impl<'a> Runner<'a> {
+	pub fn new(
+		client: &'a Client<XPredictRuntime>,
+		keystore: &'a XPredictKeystore,
+		config: &'a XPredictConfig,
+	) -> Self {
+		Runner {
+			client,
+			keystore,
+			config,
+			accounts: BTreeMap::new(),
+			variables: HashMap::new(),
+		}
+	}
+
+	/// Runs every step in order, handing each report to `on_step` as soon as
+	/// the step is done.
+	pub async fn run(
+		&mut self,
+		scenario: &Scenario,
+		mut on_step: impl FnMut(&StepReport),
+	) -> ScenarioReport {
+		self.accounts = scenario.accounts.clone();
+		self.variables.clear();
+		let mut steps = Vec::with_capacity(scenario.steps.len());
+		let mut aborted = false;
+		for (index, step) in scenario.steps.iter().enumerate() {
+			let started = Instant::now();
+			let outcome = if aborted {
+				StepOutcome::Skipped
+			} else {
+				match self.run_step(step).await {
+					Ok(detail) => StepOutcome::Passed(detail),
+					Err(e) => {
+						aborted = !step.is_assertion();
+						StepOutcome::Failed(e.to_string())
+					}
+				}
+			};
+			let report = StepReport {
+				index: index + 1,
+				action: step.action(),
+				outcome,
+				elapsed: started.elapsed(),
+			};
+			on_step(&report);
+			steps.push(report);
+		}
+		ScenarioReport {
+			name: scenario.name.clone(),
+			steps,
+		}
+	}
+
+	async fn run_step(&mut self, step: &Step) -> Result<String> {
+		let client = self.client;
+		match step {
+			Step::NewAsset {
+				signer,
+				decimals,
+				save,
+			} => {
+				let currency_id =
+					XPredictLogic::new_asset(client, &self.signer(signer)?, *decimals).await?;
+				self.variables.insert(save.clone(), currency_id);
+				Ok(format!("{} = currency {}", save, currency_id))
+			}
+			Step::Mint {
+				signer,
+				currency,
+				to,
+				amount,
+			} => {
+				let (currency_id, amount) = (self.id(currency)?, amount.value()?);
+				let account = self.account(to)?;
+				XPredictLogic::mint_token(
+					client,
+					&self.signer(signer)?,
+					currency_id,
+					&account,
+					amount,
+				)
+				.await?;
+				Ok(format!(
+					"minted {} of currency {} to {}",
+					amount, currency_id, to
+				))
+			}
+			Step::Fund {
+				signer,
+				currency,
+				to,
+				amount,
+			} => {
+				let (currency_id, amount) = (self.id(currency)?, amount.value()?);
+				let account = self.account(to)?;
+				let balance = XPredictLogic::balance_of(client, currency_id, &account).await?;
+				if balance >= amount {
+					return Ok(format!("{} already holds {}", to, balance));
+				}
+				XPredictLogic::transfer_token(
+					client,
+					&self.signer(signer)?,
+					currency_id,
+					&account,
+					amount - balance,
+				)
+				.await?;
+				Ok(format!(
+					"sent {} of currency {} to {}",
+					amount - balance,
+					currency_id,
+					to
+				))
+			}
+			Step::AutonomyStake {
+				account,
+				fund_from,
+				currency,
+			} => {
+				let minimum = XPredictLogic::autonomy_minimal_stake_number(client).await?;
+				let target = self.account(account)?;
+				let staked = XPredictLogic::autonomy_stake_account(client, &target).await?;
+				if staked >= minimum {
+					return Ok(format!("{} already staked {}", account, staked));
+				}
+				if let Some(funder) = fund_from {
+					let currency_id = match currency {
+						Some(currency) => self.id(currency)?,
+						None => XPredictLogic::autonomy_stake_currency(client)?,
+					};
+					let balance = XPredictLogic::balance_of(client, currency_id, &target).await?;
+					if balance < minimum {
+						XPredictLogic::transfer_token(
+							client,
+							&self.signer(funder)?,
+							currency_id,
+							&target,
+							minimum - balance,
+						)
+						.await?;
+					}
+				}
+				XPredictLogic::autonomy_stake(client, &self.signer(account)?).await?;
+				Ok(format!("{} staked", account))
+			}
+			Step::AutonomyTag { signer, account } => {
+				let target = self.account(account)?;
+				if XPredictLogic::autonomy_account(client, &target)
+					.await
+					.is_ok()
+				{
+					return Ok(format!("{} already tagged", account));
+				}
+				XPredictLogic::autonomy_tag(client, &self.signer(signer)?, &target).await?;
+				Ok(format!("{} tagged", account))
+			}
+			Step::NewProposal {
+				signer,
+				currency,
+				number,
+				close_after_secs,
+				earn_fee,
+				save,
+			} => {
+				let proposal_id = XPredictLogic::make_proposal(
+					client,
+					&self.signer(signer)?,
+					self.id(currency)?,
+					number.value()?,
+					Duration::from_secs(
+						close_after_secs.unwrap_or(self.config.proposal.close_after_secs),
+					),
+					earn_fee.unwrap_or(self.config.fees.earn_fee),
+				)
+				.await?;
+				let (yes, no) = XPredictLogic::proposal_pairs(client, proposal_id).await?;
+				self.variables.insert(save.clone(), proposal_id);
+				self.variables.insert(format!("{}.yes", save), yes);
+				self.variables.insert(format!("{}.no", save), no);
+				Ok(format!(
+					"{} = proposal {}, yes {}, no {}",
+					save, proposal_id, yes, no
+				))
+			}
+			Step::SetStatus {
+				signer,
+				proposal,
+				status,
+			} => {
+				let proposal_id = self.id(proposal)?;
+				XPredictLogic::proposal_set_status(
+					client,
+					&self.signer(signer)?,
+					proposal_id,
+					*status,
+				)
+				.await?;
+				Ok(format!("proposal {} is {:?}", proposal_id, status))
+			}
+			Step::Buy {
+				signer,
+				proposal,
+				side,
+				number,
+				min_output,
+			} => {
+				let proposal_id = self.id(proposal)?;
+				let currency_id = self.side(proposal_id, *side).await?;
+				let limit = TradeLimit {
+					min_output: min_output.as_ref().map_or(Ok(0), Amount::value)?,
+					price_bound: None,
+				};
+				let received = XPredictLogic::buy_with_limit(
+					client,
+					&self.signer(signer)?,
+					proposal_id,
+					currency_id,
+					number.value()?,
+					limit,
+				)
+				.await?;
+				Ok(format!("bought {} of currency {}", received, currency_id))
+			}
+			Step::Sell {
+				signer,
+				proposal,
+				side,
+				number,
+				min_output,
+			} => {
+				let proposal_id = self.id(proposal)?;
+				let currency_id = self.side(proposal_id, *side).await?;
+				let limit = TradeLimit {
+					min_output: min_output.as_ref().map_or(Ok(0), Amount::value)?,
+					price_bound: None,
+				};
+				let received = XPredictLogic::sell_with_limit(
+					client,
+					&self.signer(signer)?,
+					proposal_id,
+					currency_id,
+					number.value()?,
+					limit,
+				)
+				.await?;
+				Ok(format!("sold currency {} for {}", currency_id, received))
+			}
+			Step::AddLiquidity {
+				signer,
+				proposal,
+				number,
+			} => {
+				let added = XPredictLogic::proposal_add_liquidity(
+					client,
+					&self.signer(signer)?,
+					self.id(proposal)?,
+					number.value()?,
+				)
+				.await?;
+				Ok(format!("added {}", added))
+			}
+			Step::RemoveLiquidity {
+				signer,
+				proposal,
+				number,
+			} => {
+				let removed = XPredictLogic::proposal_remove_liquidity(
+					client,
+					&self.signer(signer)?,
+					self.id(proposal)?,
+					number.value()?,
+				)
+				.await?;
+				Ok(format!("removed {}", removed))
+			}
+			Step::WaitStatus {
+				proposal,
+				status,
+				timeout_secs,
+			} => {
+				let proposal_id = self.id(proposal)?;
+				XPredictLogic::wait_for_status(
+					client,
+					proposal_id,
+					*status,
+					Duration::from_secs(
+						timeout_secs.unwrap_or(self.config.timeouts.status_wait_secs),
+					),
+				)
+				.await?;
+				Ok(format!("proposal {} reached {:?}", proposal_id, status))
+			}
+			Step::Sleep { secs } => {
+				async_std::task::sleep(Duration::from_secs(*secs)).await;
+				Ok(format!("slept {}s", secs))
+			}
+			Step::UploadResult {
+				account,
+				proposal,
+				side,
+			} => {
+				let proposal_id = self.id(proposal)?;
+				let currency_id = self.side(proposal_id, *side).await?;
+				XPredictLogic::autonomy_upload(
+					client,
+					self.pair(account)?,
+					proposal_id,
+					currency_id,
+				)
+				.await?;
+				Ok(format!("{} uploaded {:?}", account, side))
+			}
+			Step::SetResult {
+				signer,
+				proposal,
+				side,
+			} => {
+				let proposal_id = self.id(proposal)?;
+				let currency_id = self.side(proposal_id, *side).await?;
+				XPredictLogic::proposal_set_result(
+					client,
+					&self.signer(signer)?,
+					proposal_id,
+					currency_id,
+				)
+				.await?;
+				Ok(format!("proposal {} resolved {:?}", proposal_id, side))
+			}
+			Step::AssertBalance {
+				account,
+				currency,
+				min,
+				max,
+			} => {
+				let currency_id = self.id(currency)?;
+				let balance =
+					XPredictLogic::balance_of(client, currency_id, &self.account(account)?).await?;
+				let detail = format!("{} holds {} of currency {}", account, balance, currency_id);
+				let min = min.as_ref().map(Amount::value).transpose()?;
+				let max = max.as_ref().map(Amount::value).transpose()?;
+				if min.map_or(false, |min| balance < min) || max.map_or(false, |max| balance > max)
+				{
+					return Err(XPredictError::Scenario(format!(
+						"{}, expected between {:?} and {:?}",
+						detail, min, max
+					)));
+				}
+				Ok(detail)
+			}
+			Step::AssertStatus { proposal, status } => {
+				let proposal_id = self.id(proposal)?;
+				let found = XPredictLogic::proposal_status(client, proposal_id).await?;
+				if found != *status {
+					return Err(XPredictError::UnexpectedStatus {
+						proposal_id,
+						expected: *status,
+						found,
+					});
+				}
+				Ok(format!("proposal {} is {:?}", proposal_id, found))
+			}
+		}
+	}
+
+	fn pair(&self, name: &str) -> Result<&'a Sr25519Pair> {
+		let account = self
+			.accounts
+			.get(name)
+			.ok_or_else(|| XPredictError::Scenario(format!("unknown account {:?}", name)))?;
+		self.keystore
+			.get_pair(account.authority, account.index)
+			.ok_or_else(|| {
+				XPredictError::Scenario(format!(
+					"no {:?} key with index {} for account {:?}",
+					account.authority, account.index, name
+				))
+			})
+	}
+
+	fn signer(&self, name: &str) -> Result<PairSigner<XPredictRuntime, Sr25519Pair>> {
+		self.pair(name)
+			.map(<XPredictKeystore as XPredictPairs<XPredictRuntime>>::get_signer)
+	}
+
+	fn account(&self, name: &str) -> Result<AccountId> {
+		Ok(self.pair(name)?.public().into())
+	}
+
+	fn id(&self, id: &Id) -> Result<u32> {
+		match id {
+			Id::Literal(id) => Ok(*id),
+			Id::Variable(name) => self
+				.variables
+				.get(name)
+				.copied()
+				.ok_or_else(|| XPredictError::Scenario(format!("unknown variable {:?}", name))),
+		}
+	}
+
+	async fn side(&self, proposal_id: ProposalId, side: Side) -> Result<CurrencyId> {
+		let pairs = XPredictLogic::proposal_pairs(self.client, proposal_id).await?;
+		Ok(side.currency(pairs))
+	}
+}